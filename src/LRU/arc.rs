@@ -0,0 +1,219 @@
+// ARC（Adaptive Replacement Cache）自适应替换缓存
+// t1 保存只访问过一次的数据，t2 保存访问过多次的数据，
+// b1、b2 是幽灵链表，只记录最近从 t1、t2 淘汰的键，不保存值。
+// 新数据命中 b1 说明 t1 太小，命中 b2 说明 t2 太小，据此调整 t1 的目标大小 p，
+// 所以一次性的顺序扫描只会冲刷 t1，不会把 t2 中的热点数据挤出去
+
+use std::hash::Hash;
+use crate::LRU::lru::LRU_Cache;
+use crate::LRU::policy::CachePolicy;
+
+#[allow(non_camel_case_types)]
+pub struct ARC_Cache<K, V> {
+    cap:usize,
+    p:usize,                    // t1 的目标大小
+    t1:LRU_Cache<K, V>,
+    t2:LRU_Cache<K, V>,
+    b1:LRU_Cache<K, ()>,
+    b2:LRU_Cache<K, ()>,
+}
+
+impl<K: Clone + Hash + Eq, V> ARC_Cache<K, V> {
+    pub fn with_capacity(cap:usize) -> Self{
+        ARC_Cache{
+            cap,
+            p:0,
+            t1:LRU_Cache::with_capacity(cap),
+            t2:LRU_Cache::with_capacity(cap),
+            b1:LRU_Cache::with_capacity(cap),
+            b2:LRU_Cache::with_capacity(cap),
+        }
+    }
+
+    pub fn len(&self) -> usize{
+        self.t1.len() + self.t2.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+
+    pub fn cap(&self) -> usize{
+        self.cap
+    }
+
+    // 当前 t1 的目标大小
+    pub fn target(&self) -> usize{
+        self.p
+    }
+
+    // 腾出一个位置：t1 超过目标时淘汰 t1 的尾部，否则淘汰 t2 的尾部，被淘汰的键进入幽灵链表
+    fn replace(&mut self, in_b2:bool){
+        let from_t1 = !self.t1.is_empty()
+            && ((in_b2 && self.t1.len() == self.p) || self.t1.len() > self.p || self.t2.is_empty());
+        if from_t1 {
+            if let Some((k, _)) = self.t1.pop_lru() {
+                self.b1.insert(k, ());
+            }
+        } else if let Some((k, _)) = self.t2.pop_lru() {
+            self.b2.insert(k, ());
+        }
+    }
+
+    pub fn insert(&mut self, key:K, val:V) -> Option<V>{
+        if self.cap == 0 {
+            return None;
+        }
+
+        // 已在缓存中：更新值，视为又一次访问，移到 t2
+        if let Some(old) = self.t1.remove(&key) {
+            self.t2.insert(key, val);
+            return Some(old);
+        }
+        if self.t2.contains(&key) {
+            return self.t2.insert(key, val);
+        }
+
+        if self.b1.contains(&key) {
+            // 命中 b1：增大 t1 的目标
+            let delta = (self.b2.len() / self.b1.len()).max(1);
+            self.p = (self.p + delta).min(self.cap);
+            self.b1.remove(&key);
+            if self.len() >= self.cap {
+                self.replace(false);
+            }
+            self.t2.insert(key, val);
+        } else if self.b2.contains(&key) {
+            // 命中 b2：减小 t1 的目标
+            let delta = (self.b1.len() / self.b2.len()).max(1);
+            self.p = self.p.saturating_sub(delta);
+            self.b2.remove(&key);
+            if self.len() >= self.cap {
+                self.replace(true);
+            }
+            self.t2.insert(key, val);
+        } else {
+            // 全新的键
+            if self.t1.len() + self.b1.len() >= self.cap {
+                if self.t1.len() < self.cap {
+                    self.b1.pop_lru();
+                    if self.len() >= self.cap {
+                        self.replace(false);
+                    }
+                } else {
+                    self.t1.pop_lru();
+                }
+            } else if self.len() + self.b1.len() + self.b2.len() >= self.cap {
+                if self.len() + self.b1.len() + self.b2.len() >= 2 * self.cap {
+                    self.b2.pop_lru();
+                }
+                if self.len() >= self.cap {
+                    self.replace(false);
+                }
+            }
+            self.t1.insert(key, val);
+        }
+        None
+    }
+
+    pub fn get(&mut self, key:&K) -> Option<&V>{
+        // t1 中命中的数据被访问了第二次，移到 t2
+        if let Some(val) = self.t1.remove(key) {
+            self.t2.insert(key.clone(), val);
+        }
+        self.t2.get(key)
+    }
+
+    pub fn peek(&self, key:&K) -> Option<&V>{
+        self.t1.peek(key).or_else(|| self.t2.peek(key))
+    }
+
+    pub fn remove(&mut self, key:&K) -> Option<V>{
+        self.t1.remove(key).or_else(|| self.t2.remove(key))
+    }
+
+    pub fn contains(&self, key:&K) -> bool{
+        self.t1.contains(key) || self.t2.contains(key)
+    }
+
+    pub fn clear(&mut self){
+        self.p = 0;
+        self.t1.clear();
+        self.t2.clear();
+        self.b1.clear();
+        self.b2.clear();
+    }
+}
+
+impl<K: Clone + Hash + Eq, V> CachePolicy<K, V> for ARC_Cache<K, V> {
+    fn insert(&mut self, key:K, val:V) -> Option<V>{
+        ARC_Cache::insert(self, key, val)
+    }
+
+    fn get(&mut self, key:&K) -> Option<&V>{
+        ARC_Cache::get(self, key)
+    }
+
+    fn peek(&self, key:&K) -> Option<&V>{
+        ARC_Cache::peek(self, key)
+    }
+
+    fn remove(&mut self, key:&K) -> Option<V>{
+        ARC_Cache::remove(self, key)
+    }
+
+    fn contains(&self, key:&K) -> bool{
+        ARC_Cache::contains(self, key)
+    }
+
+    fn len(&self) -> usize{
+        ARC_Cache::len(self)
+    }
+
+    fn cap(&self) -> usize{
+        ARC_Cache::cap(self)
+    }
+
+    fn clear(&mut self){
+        ARC_Cache::clear(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn it_works() {
+        let mut cache = ARC_Cache::with_capacity(2);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        assert_eq!(cache.get(&1), Some(&"a"));
+
+        // 1 在 t2，新数据只会挤掉 t1 中的 2
+        cache.insert(3, "c");
+        assert!(cache.contains(&1) && !cache.contains(&2));
+        assert_eq!(cache.len(), 2);
+
+        // 2 命中 b1，t1 的目标增大
+        cache.insert(2, "b");
+        assert_eq!(cache.target(), 1);
+        assert!(cache.contains(&2));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.peek(&2), Some(&"b"));
+        assert_eq!(cache.insert(2, "bb"), Some("b"));
+    }
+
+    #[test]
+    fn test_bounded() {
+        let mut cache = ARC_Cache::with_capacity(8);
+        for i in 0..10_000u32 {
+            let key = (i * 7919) % 37 + if i % 3 == 0 { 0 } else { 100 + i % 11 };
+            if cache.get(&key).is_none() {
+                cache.insert(key, i);
+            }
+            assert!(cache.len() <= 8);
+            assert!(cache.t1.len() + cache.b1.len() <= 8);
+            assert!(cache.len() + cache.b1.len() + cache.b2.len() <= 16);
+        }
+    }
+}
@@ -0,0 +1,655 @@
+// CLOCK 缓存，近似 LRU
+// 数据项围成一个环，访问时只设置引用位，不移动数据项，所以 get 只需 &self。
+// 需要淘汰时指针沿环转动：引用位为 1 的清零并跳过（再给一次机会），遇到引用位为 0 的就淘汰。
+// 公开接口与 LRU_Cache 相同（快照除外），过期时间、淘汰回调和统计的含义也相同，
+// 只是 peek_lru/pop_lru 对应下一个被淘汰的数据项，而不是严格的最久未使用。
+// get 只需 &self，读到过期的数据项时当作不存在，留到淘汰或 purge_expired 时再删除
+
+use std::hash::Hash;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::LRU::lru::{Clock, SystemClock, CacheStats, SharedStats, SyncListener, RemovalCause, Fetched};
+use crate::LRU::policy::CachePolicy;
+
+// 缓存容量
+const CACHE_SIZE:usize = 100;
+
+// 数据项
+struct Slot<K, V> {
+    key:K,
+    val:V,
+    referenced:AtomicBool,
+    expires:Option<Duration>,   // 过期时刻，None 表示永不过期
+}
+
+#[allow(non_camel_case_types)]
+pub struct CLOCK_Cache<K, V> {
+    cap:usize,
+    hand:usize,                 // 下一个检查的位置
+    map:HashMap<K, usize>,
+    slots:Vec<Slot<K, V>>,
+    ttl:Option<Duration>,       // 默认存活时间
+    clock:Arc<dyn Clock>,
+    listener:Option<SyncListener<K, V>>,
+    stats:SharedStats,
+}
+
+impl<K: Clone + Hash + Eq, V> CLOCK_Cache<K, V> {
+    pub fn new() -> Self{
+        Self::with_capacity(CACHE_SIZE)
+    }
+
+    pub fn with_capacity(cap:usize) -> Self{
+        CLOCK_Cache{
+            cap,
+            hand:0,
+            map:HashMap::with_capacity(cap),
+            slots:Vec::with_capacity(cap),
+            ttl:None,
+            clock:Arc::new(SystemClock::new()),
+            listener:None,
+            stats:SharedStats::default(),
+        }
+    }
+
+    // 所有数据项默认存活 ttl
+    pub fn with_ttl(cap:usize, ttl:Duration) -> Self{
+        let mut cache = Self::with_capacity(cap);
+        cache.ttl = Some(ttl);
+        cache
+    }
+
+    pub fn set_ttl(&mut self, ttl:Option<Duration>){
+        self.ttl = ttl;
+    }
+
+    // 更换时钟，已有数据项的过期时刻按新时钟解释
+    pub fn set_clock(&mut self, clock:Arc<dyn Clock>){
+        self.clock = clock;
+    }
+
+    // 注册数据项离开缓存时的回调
+    pub fn set_eviction_listener<F>(&mut self, f:F)
+        where F: FnMut(K, V, RemovalCause) + Send + Sync + 'static {
+        self.listener = Some(Box::new(f));
+    }
+
+    fn notify(&mut self, key:K, val:V, cause:RemovalCause){
+        if let Some(f) = self.listener.as_mut() {
+            f(key, val, cause);
+        }
+    }
+
+    // 统计数据快照
+    pub fn stats(&self) -> CacheStats{
+        self.stats.snapshot()
+    }
+
+    pub fn reset_stats(&mut self){
+        self.stats = SharedStats::default();
+    }
+
+    pub fn len(&self) -> usize{
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.slots.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool{
+        self.slots.len() >= self.cap
+    }
+
+    pub fn cap(&self) -> usize{
+        self.cap
+    }
+
+    fn is_expired(&self, index:usize) -> bool{
+        match self.slots[index].expires {
+            Some(t) => self.clock.now() >= t,
+            None => false,
+        }
+    }
+
+    // 转动指针找到要淘汰的位置：过期的或引用位为 0 的，调用时环不能为空。
+    // 持有 &mut self 时 get 不会同时设置引用位，第一圈把引用位都清零，所以最多转两圈
+    fn find_victim(&mut self) -> usize{
+        loop {
+            if self.hand >= self.slots.len() {
+                self.hand = 0;
+            }
+            if self.is_expired(self.hand) {
+                return self.hand;
+            }
+            let slot = &mut self.slots[self.hand];
+            if !*slot.referenced.get_mut() {
+                return self.hand;
+            }
+            *slot.referenced.get_mut() = false;
+            self.hand += 1;
+        }
+    }
+
+    // 删除数据项并把值交给回调，过期的数据项总是以 Expired 通知
+    fn discard(&mut self, index:usize, cause:RemovalCause){
+        let cause = if self.is_expired(index) { RemovalCause::Expired } else { cause };
+        if cause == RemovalCause::Capacity || cause == RemovalCause::Expired {
+            self.stats.evicted();
+        }
+        let (key, val) = self.remove_index(index);
+        self.notify(key, val, cause);
+    }
+
+    // 删除数据项，最后一个数据项移过来填补空位。
+    // 被移动的数据项在环上的位置随之改变，所以环上的顺序不是插入顺序
+    fn remove_index(&mut self, index:usize) -> (K, V){
+        let slot = self.slots.swap_remove(index);
+        self.map.remove(&slot.key);
+        if let Some(moved) = self.slots.get(index) {
+            self.map.insert(moved.key.clone(), index);
+        }
+        (slot.key, slot.val)
+    }
+
+    // 插入，被替换的旧值以 Replaced 交给回调，没有回调时返回旧值
+    pub fn insert(&mut self, key:K, val:V) -> Option<V>{
+        let ttl = self.ttl;
+        self.insert_entry(key, val, ttl, self.replaced())
+    }
+
+    // 插入并单独指定存活时间
+    pub fn insert_with_ttl(&mut self, key:K, val:V, ttl:Duration) -> Option<V>{
+        self.insert_entry(key, val, Some(ttl), self.replaced())
+    }
+
+    // 插入并返回被替换的旧值，旧值不交给回调
+    pub fn insert_silent(&mut self, key:K, val:V) -> Option<V>{
+        let ttl = self.ttl;
+        self.insert_entry(key, val, ttl, None)
+    }
+
+    // 插入，被替换的旧值交给回调而不是返回
+    pub fn put(&mut self, key:K, val:V){
+        let ttl = self.ttl;
+        self.insert_entry(key, val, ttl, Some(RemovalCause::Replaced));
+    }
+
+    // 有回调时被替换的旧值交给回调
+    fn replaced(&self) -> Option<RemovalCause>{
+        self.listener.as_ref().map(|_| RemovalCause::Replaced)
+    }
+
+    // replaced 为 Some 时旧值交给回调，否则返回给调用者
+    fn insert_entry(&mut self, key:K, val:V, ttl:Option<Duration>,
+        replaced:Option<RemovalCause>) -> Option<V>{
+        let expires = ttl.map(|t| self.clock.now() + t);
+
+        // 已经在缓存中就原地替换并设置引用位，原来的值已过期时当作不存在
+        if let Some(&index) = self.map.get(&key) {
+            let expired = self.is_expired(index);
+            if expired {
+                self.stats.evicted();
+                self.stats.inserted();
+            } else {
+                self.stats.updated();
+            }
+            let slot = &mut self.slots[index];
+            *slot.referenced.get_mut() = true;
+            slot.expires = expires;
+            let old_val = std::mem::replace(&mut slot.val, val);
+            match (expired, replaced) {
+                (false, None) => return Some(old_val),
+                (false, Some(cause)) => self.notify(key, old_val, cause),
+                (true, _) => self.notify(key, old_val, RemovalCause::Expired),
+            }
+            return None;
+        }
+
+        self.stats.inserted();
+        if self.cap == 0 {
+            self.stats.evicted();
+            self.notify(key, val, RemovalCause::Capacity);
+            return None;
+        }
+
+        // 满了就在指针所指的位置放入新数据
+        let slot = Slot{ key:key.clone(), val, referenced:AtomicBool::new(false), expires };
+        if self.is_full() {
+            let index = self.find_victim();
+            let cause = if self.is_expired(index) { RemovalCause::Expired } else { RemovalCause::Capacity };
+            let old = std::mem::replace(&mut self.slots[index], slot);
+            self.map.remove(&old.key);
+            self.map.insert(key, index);
+            self.hand = index + 1;
+            self.stats.evicted();
+            self.notify(old.key, old.val, cause);
+        } else {
+            self.slots.push(slot);
+            self.map.insert(key, self.slots.len() - 1);
+        }
+        None
+    }
+
+    // 查找未过期的数据项，记录命中和未命中
+    fn lookup(&self, key:&K) -> Option<usize>{
+        match self.map.get(key) {
+            Some(&index) if !self.is_expired(index) => {
+                self.stats.hit();
+                Some(index)
+            },
+            _ => {
+                self.stats.miss();
+                None
+            },
+        }
+    }
+
+    // 读取数据项并设置引用位
+    pub fn get(&self, key:&K) -> Option<&V>{
+        let slot = &self.slots[self.lookup(key)?];
+        slot.referenced.store(true, Ordering::Relaxed);
+        Some(&slot.val)
+    }
+
+    pub fn get_mut(&mut self, key:&K) -> Option<&mut V>{
+        let index = self.lookup(key)?;
+        let slot = &mut self.slots[index];
+        *slot.referenced.get_mut() = true;
+        Some(&mut slot.val)
+    }
+
+    // 键不存在时用 f 计算值并插入，返回缓存中的值；容量为 0 时不缓存，返回计算结果
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key:K, f:F) -> Fetched<'_, V>{
+        match self.try_get_or_insert_with(key, || Ok::<V, ()>(f())) {
+            Ok(v) => v,
+            Err(()) => unreachable!(),
+        }
+    }
+
+    // 同 get_or_insert_with，计算失败时不插入并返回错误
+    pub fn try_get_or_insert_with<E, F>(&mut self, key:K, f:F) -> Result<Fetched<'_, V>, E>
+        where F: FnOnce() -> Result<V, E> {
+        let index = match self.lookup(&key) {
+            Some(index) => {
+                *self.slots[index].referenced.get_mut() = true;
+                index
+            },
+            None if self.cap == 0 => {
+                return Ok(Fetched::Computed(f()?));
+            },
+            None => {
+                self.insert(key.clone(), f()?);
+                self.map[&key]
+            },
+        };
+        Ok(Fetched::Cached(&self.slots[index].val))
+    }
+
+    // 读取数据项，不设置引用位
+    pub fn peek(&self, key:&K) -> Option<&V>{
+        match self.map.get(key) {
+            Some(&index) if !self.is_expired(index) => Some(&self.slots[index].val),
+            _ => None,
+        }
+    }
+
+    // 下一个会被淘汰的未过期数据项，不转动指针也不清除引用位。
+    // 从指针处找第一个引用位为 0 的，都被访问过时就是指针之后第一个未过期的
+    pub fn peek_lru(&self) -> Option<(&K, &V)>{
+        let len = self.slots.len();
+        let start = if self.hand >= len { 0 } else { self.hand };
+        let mut live = (0..len)
+            .map(|i| (start + i) % len)
+            .filter(|&i| !self.is_expired(i));
+        let first = live.next()?;
+        let index = std::iter::once(first).chain(live)
+            .find(|&i| !self.slots[i].referenced.load(Ordering::Relaxed))
+            .unwrap_or(first);
+        let slot = &self.slots[index];
+        Some((&slot.key, &slot.val))
+    }
+
+    // 按 CLOCK 策略淘汰一个未过期的数据项并返回，途经的过期项一并删除
+    pub fn pop_lru(&mut self) -> Option<(K, V)>{
+        while !self.slots.is_empty() {
+            let index = self.find_victim();
+            if !self.is_expired(index) {
+                return Some(self.remove_index(index));
+            }
+            self.discard(index, RemovalCause::Expired);
+        }
+        None
+    }
+
+    // 删除，值以 Explicit 交给回调，没有回调时返回值
+    pub fn remove(&mut self, key:&K) -> Option<V>{
+        if self.listener.is_some() {
+            self.invalidate(key);
+            return None;
+        }
+        self.remove_silent(key)
+    }
+
+    // 删除并返回值，值不交给回调
+    pub fn remove_silent(&mut self, key:&K) -> Option<V>{
+        let index = *self.map.get(key)?;
+        if self.is_expired(index) {
+            self.discard(index, RemovalCause::Expired);
+            return None;
+        }
+        Some(self.remove_index(index).1)
+    }
+
+    // 主动删除，值交给回调，返回键是否存在
+    pub fn invalidate(&mut self, key:&K) -> bool{
+        match self.map.get(key) {
+            Some(&index) => {
+                let live = !self.is_expired(index);
+                self.discard(index, RemovalCause::Explicit);
+                live
+            },
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, key:&K) -> bool{
+        self.peek(key).is_some()
+    }
+
+    // 清理所有过期的数据项，返回清理的数量。
+    // 从后往前删除，被移到空位上的数据项都已经检查过
+    pub fn purge_expired(&mut self) -> usize{
+        let mut count = 0;
+        for index in (0..self.slots.len()).rev() {
+            if index < self.slots.len() && self.is_expired(index) {
+                self.discard(index, RemovalCause::Expired);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    // 调整容量，缩小时按 CLOCK 策略淘汰
+    pub fn resize(&mut self, cap:usize){
+        self.cap = cap;
+        while self.len() > cap {
+            let index = self.find_victim();
+            self.discard(index, RemovalCause::Capacity);
+        }
+    }
+
+    // 清空缓存，有回调时所有值都交给回调
+    pub fn clear(&mut self){
+        if self.listener.is_some() {
+            while !self.slots.is_empty() {
+                self.discard(self.slots.len() - 1, RemovalCause::Explicit);
+            }
+        }
+        self.hand = 0;
+        self.map.clear();
+        self.slots.clear();
+    }
+
+    // 取出全部未过期的数据项，缓存被清空，过期的交给回调。
+    // 从指针处开始按环上的位置排列，不考虑引用位，只是大致的淘汰顺序
+    pub fn drain(&mut self) -> std::vec::IntoIter<(K, V)>{
+        let start = if self.hand >= self.slots.len() { 0 } else { self.hand };
+        let now = self.clock.now();
+        let mut slots = std::mem::take(&mut self.slots);
+        slots.rotate_left(start);
+        self.hand = 0;
+        self.map.clear();
+        let mut items = Vec::with_capacity(slots.len());
+        for slot in slots {
+            if slot.expires.map_or(false, |t| now >= t) {
+                self.stats.evicted();
+                self.notify(slot.key, slot.val, RemovalCause::Expired);
+            } else {
+                items.push((slot.key, slot.val));
+            }
+        }
+        items.into_iter()
+    }
+
+    // 按环上的位置遍历，跳过过期的数据项
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)>{
+        let now = self.clock.now();
+        self.slots.iter()
+            .filter(move |s| s.expires.map_or(true, |t| now < t))
+            .map(|s| (&s.key, &s.val))
+    }
+}
+
+impl<K: Clone + Hash + Eq, V> Default for CLOCK_Cache<K, V> {
+    fn default() -> Self{
+        Self::new()
+    }
+}
+
+impl<K: Clone + Hash + Eq, V> CachePolicy<K, V> for CLOCK_Cache<K, V> {
+    fn insert(&mut self, key:K, val:V) -> Option<V>{
+        CLOCK_Cache::insert(self, key, val)
+    }
+
+    fn get(&mut self, key:&K) -> Option<&V>{
+        CLOCK_Cache::get(self, key)
+    }
+
+    fn peek(&self, key:&K) -> Option<&V>{
+        CLOCK_Cache::peek(self, key)
+    }
+
+    fn remove(&mut self, key:&K) -> Option<V>{
+        CLOCK_Cache::remove(self, key)
+    }
+
+    fn contains(&self, key:&K) -> bool{
+        CLOCK_Cache::contains(self, key)
+    }
+
+    fn len(&self) -> usize{
+        CLOCK_Cache::len(self)
+    }
+
+    fn cap(&self) -> usize{
+        CLOCK_Cache::cap(self)
+    }
+
+    fn clear(&mut self){
+        CLOCK_Cache::clear(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::LRU::lru::ManualClock;
+    #[test]
+    fn it_works() {
+        let mut cache = CLOCK_Cache::with_capacity(3);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.insert("c", 3);
+
+        // 只读引用就能访问，a 和 c 获得第二次机会，b 被淘汰
+        let shared = &cache;
+        assert_eq!(shared.get(&"a"), Some(&1));
+        assert_eq!(shared.get(&"c"), Some(&3));
+        cache.insert("d", 4);
+        assert!(!cache.contains(&"b"));
+        assert_eq!(cache.len(), 3);
+
+        // 指针停在 c，c 被访问过，清零后跳过；a 的引用位在上一轮已清零，淘汰 a
+        cache.insert("e", 5);
+        assert!(!cache.contains(&"a"));
+        assert_eq!(cache.insert("c", 30), Some(3));
+        assert_eq!(cache.peek(&"c"), Some(&30));
+        assert_eq!(cache.remove(&"d"), Some(4));
+        assert_eq!(cache.iter().count(), 2);
+
+        cache.resize(1);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains(&"c"));
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_pop_drain() {
+        let mut cache = CLOCK_Cache::with_capacity(3);
+        assert_eq!(cache.peek_lru(), None);
+        assert_eq!(cache.pop_lru(), None);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        cache.insert(3, "c");
+
+        // peek_lru 不清除引用位，和随后 pop_lru 淘汰的一致
+        cache.get(&1);
+        assert_eq!(cache.peek_lru(), Some((&2, &"b")));
+        assert_eq!(cache.peek_lru(), Some((&2, &"b")));
+        assert_eq!(cache.pop_lru(), Some((2, "b")));
+
+        // 都被访问过时转一圈后淘汰指针所指的
+        cache.get(&1);
+        cache.get(&3);
+        let (&k, _) = cache.peek_lru().unwrap();
+        assert_eq!(cache.pop_lru().map(|(k, _)| k), Some(k));
+
+        cache.insert(4, "d");
+        let items: Vec<_> = cache.drain().collect();
+        assert_eq!(items.len(), 2);
+        assert!(cache.is_empty() && cache.peek_lru().is_none());
+        cache.insert(5, "e");
+        assert_eq!(cache.drain().collect::<Vec<_>>(), vec![(5, "e")]);
+    }
+
+    #[test]
+    fn test_churn_bounded() {
+        let mut cache = CLOCK_Cache::with_capacity(16);
+        for i in 0..10_000 {
+            if cache.get(&(i % 40)).is_none() {
+                cache.insert(i % 40, i);
+            }
+            if i % 7 == 0 {
+                cache.remove(&(i % 13));
+            }
+            assert!(cache.len() <= 16);
+            assert!(cache.map.iter().all(|(k, &i)| cache.slots[i].key == *k));
+        }
+    }
+
+    #[test]
+    fn test_ttl() {
+        let clock = Arc::new(ManualClock::new());
+        let mut cache = CLOCK_Cache::with_ttl(3, Duration::from_secs(10));
+        cache.set_clock(clock.clone());
+
+        cache.insert("a", 1);
+        cache.insert_with_ttl("b", 2, Duration::from_secs(30));
+        clock.advance(Duration::from_secs(5));
+        cache.insert("c", 3);
+        assert_eq!(cache.get(&"a"), Some(&1));
+
+        // a 过期后读不到，但仍占着位置，满了之后先淘汰它
+        clock.advance(Duration::from_secs(6));
+        assert_eq!(cache.get(&"a"), None);
+        assert!(!cache.contains(&"a"));
+        assert_eq!(cache.iter().count(), 2);
+        cache.get(&"b");
+        cache.get(&"c");
+        cache.insert("d", 4);
+        assert_eq!(cache.len(), 3);
+        assert!(cache.contains(&"b") && cache.contains(&"c"));
+
+        // b 被访问过，d 先被淘汰；pop_lru 途经过期的 c 时把它删除
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(cache.peek_lru(), Some((&"d", &4)));
+        assert_eq!(cache.pop_lru(), Some(("d", 4)));
+        assert_eq!(cache.pop_lru(), Some(("b", 2)));
+        assert!(cache.is_empty());
+
+        cache.insert("e", 5);
+        cache.insert("f", 6);
+        clock.advance(Duration::from_secs(10));
+        cache.insert_with_ttl("g", 7, Duration::from_secs(30));
+        assert_eq!(cache.purge_expired(), 2);
+        assert_eq!(cache.drain().collect::<Vec<_>>(), vec![("g", 7)]);
+    }
+
+    #[test]
+    fn test_eviction_listener() {
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let clock = Arc::new(ManualClock::new());
+        let mut cache = CLOCK_Cache::with_capacity(2);
+        cache.set_clock(clock.clone());
+        let log = removed.clone();
+        cache.set_eviction_listener(move |k, v, cause| log.lock().unwrap().push((k, v, cause)));
+
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.insert("c", 3);
+        cache.put("b", 20);
+        assert_eq!(cache.insert("b", 200), None);
+        assert!(cache.invalidate(&"c"));
+        cache.insert_with_ttl("d", 4, Duration::from_secs(1));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(cache.get(&"d"), None);
+        assert_eq!(cache.remove(&"d"), None);
+        assert_eq!(cache.remove(&"b"), None);
+        cache.insert("e", 5);
+        assert_eq!(cache.insert_silent("e", 50), Some(5));
+        cache.clear();
+
+        assert_eq!(*removed.lock().unwrap(), vec![
+            ("a", 1, RemovalCause::Capacity),
+            ("b", 2, RemovalCause::Replaced),
+            ("b", 20, RemovalCause::Replaced),
+            ("c", 3, RemovalCause::Explicit),
+            ("d", 4, RemovalCause::Expired),
+            ("b", 200, RemovalCause::Explicit),
+            ("e", 50, RemovalCause::Explicit),
+        ]);
+    }
+
+    #[test]
+    fn test_stats() {
+        let clock = Arc::new(ManualClock::new());
+        let mut cache = CLOCK_Cache::with_capacity(2);
+        cache.set_clock(clock.clone());
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        cache.insert(2, 20);
+        cache.insert(3, 3);
+        cache.get(&3);
+        cache.get(&1);
+        cache.peek(&2);
+        cache.insert_with_ttl(4, 4, Duration::from_secs(1));
+        clock.advance(Duration::from_secs(1));
+        cache.get(&4);
+
+        assert_eq!(cache.stats(), CacheStats{
+            hits:1, misses:2, inserts:4, updates:1, evictions:2,
+        });
+        cache.reset_stats();
+        assert_eq!(cache.stats(), CacheStats::default());
+    }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        let mut cache = CLOCK_Cache::with_capacity(2);
+        assert_eq!(cache.get_or_insert_with(1, || 10), Fetched::Cached(&10));
+        assert_eq!(*cache.get_or_insert_with(1, || 11), 10);
+        assert_eq!(cache.try_get_or_insert_with(2, || Err::<i32, _>("failed")), Err("failed"));
+        assert!(!cache.contains(&2));
+        assert_eq!(cache.stats().hits, 1);
+
+        // 容量为 0 时不缓存，直接返回计算结果
+        let mut cache = CLOCK_Cache::with_capacity(0);
+        assert_eq!(cache.get_or_insert_with(1, || 10), Fetched::Computed(10));
+        assert!(cache.is_empty());
+    }
+}
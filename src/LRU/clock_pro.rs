@@ -0,0 +1,953 @@
+// CLOCK-Pro 缓存（Jiang, Chen, Zhang 2005）
+// 和 CLOCK 一样访问时只设置引用位，get 只需 &self。数据分为热数据和冷数据，
+// 新数据先作为冷数据进入测试期，测试期内再次被访问就升级为热数据。
+// 测试期内被淘汰的冷数据只保留键（非驻留），在测试期结束前再次访问说明冷数据区太小，
+// 据此调整冷数据区的目标大小 cold_target，所以一次性的扫描只会冲刷冷数据。
+// 三个指针沿同一个环转动：
+//   hand_cold 淘汰冷数据；hand_hot 把未被访问的热数据降级为冷数据；
+//   hand_test 结束冷数据的测试期，并删除多余的非驻留键（最多 cap 个）
+// 过期时间、淘汰回调和统计与 LRU_Cache 相同。过期的数据项读不到，
+// 冷数据在 hand_cold 经过时直接删除（不留非驻留的键），热数据要先降级为冷数据
+
+use std::hash::Hash;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::LRU::lru::{Clock, SystemClock, CacheStats, SharedStats, SyncListener, RemovalCause, Fetched};
+use crate::LRU::policy::CachePolicy;
+
+// 缓存容量
+const CACHE_SIZE:usize = 100;
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+enum Status {
+    Hot,
+    Cold,
+    NonResident,        // 已被淘汰，只保留键，一定处在测试期
+}
+
+// 环上的节点
+struct Node<K, V> {
+    key:K,
+    val:Option<V>,
+    status:Status,
+    test:bool,                  // 是否在测试期
+    referenced:AtomicBool,
+    expires:Option<Duration>,   // 过期时刻，None 表示永不过期
+    prev:usize,
+    next:usize,
+}
+
+#[allow(non_camel_case_types)]
+pub struct CLOCKPro_Cache<K, V> {
+    cap:usize,
+    cold_target:usize,          // 冷数据区的目标大小，热数据最多 cap - cold_target 个
+    map:HashMap<K, usize>,      // 包括非驻留的键
+    nodes:Vec<Node<K, V>>,
+    free:Vec<usize>,
+    hand_hot:Option<usize>,     // 环的头部在 hand_hot 之前
+    hand_cold:Option<usize>,
+    hand_test:Option<usize>,
+    count_hot:usize,
+    count_cold:usize,
+    count_test:usize,           // 非驻留的键数
+    ttl:Option<Duration>,       // 默认存活时间
+    clock:Arc<dyn Clock>,
+    listener:Option<SyncListener<K, V>>,
+    stats:SharedStats,
+}
+
+impl<K: Clone + Hash + Eq, V> CLOCKPro_Cache<K, V> {
+    pub fn new() -> Self{
+        Self::with_capacity(CACHE_SIZE)
+    }
+
+    pub fn with_capacity(cap:usize) -> Self{
+        CLOCKPro_Cache{
+            cap,
+            cold_target:cap,
+            map:HashMap::with_capacity(cap * 2),
+            nodes:Vec::with_capacity(cap * 2),
+            free:Vec::new(),
+            hand_hot:None,
+            hand_cold:None,
+            hand_test:None,
+            count_hot:0,
+            count_cold:0,
+            count_test:0,
+            ttl:None,
+            clock:Arc::new(SystemClock::new()),
+            listener:None,
+            stats:SharedStats::default(),
+        }
+    }
+
+    // 所有数据项默认存活 ttl
+    pub fn with_ttl(cap:usize, ttl:Duration) -> Self{
+        let mut cache = Self::with_capacity(cap);
+        cache.ttl = Some(ttl);
+        cache
+    }
+
+    pub fn set_ttl(&mut self, ttl:Option<Duration>){
+        self.ttl = ttl;
+    }
+
+    // 更换时钟，已有数据项的过期时刻按新时钟解释
+    pub fn set_clock(&mut self, clock:Arc<dyn Clock>){
+        self.clock = clock;
+    }
+
+    // 注册数据项离开缓存时的回调，非驻留的键被删除时不通知
+    pub fn set_eviction_listener<F>(&mut self, f:F)
+        where F: FnMut(K, V, RemovalCause) + Send + Sync + 'static {
+        self.listener = Some(Box::new(f));
+    }
+
+    fn notify(&mut self, key:K, val:V, cause:RemovalCause){
+        if let Some(f) = self.listener.as_mut() {
+            f(key, val, cause);
+        }
+    }
+
+    // 统计数据快照
+    pub fn stats(&self) -> CacheStats{
+        self.stats.snapshot()
+    }
+
+    pub fn reset_stats(&mut self){
+        self.stats = SharedStats::default();
+    }
+
+    pub fn len(&self) -> usize{
+        self.count_hot + self.count_cold
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool{
+        self.len() >= self.cap
+    }
+
+    pub fn cap(&self) -> usize{
+        self.cap
+    }
+
+    // 当前冷数据区的目标大小
+    pub fn cold_target(&self) -> usize{
+        self.cold_target
+    }
+
+    fn hot_target(&self) -> usize{
+        self.cap - self.cold_target
+    }
+
+    // 驻留且已过期
+    fn is_expired(&self, index:usize) -> bool{
+        let node = &self.nodes[index];
+        match node.expires {
+            Some(t) => node.val.is_some() && self.clock.now() >= t,
+            None => false,
+        }
+    }
+
+    // 从环的头部开始的所有节点
+    fn ring(&self) -> Vec<usize>{
+        let mut indices = Vec::with_capacity(self.map.len());
+        let mut cur = self.hand_hot;
+        for _ in 0..self.map.len() {
+            let index = match cur {
+                Some(index) => index,
+                None => break,
+            };
+            indices.push(index);
+            cur = Some(self.nodes[index].next);
+        }
+        indices
+    }
+
+    // 放到环的头部，也就是 hand_hot 的前面
+    fn link(&mut self, index:usize){
+        match self.hand_hot {
+            None => {
+                self.nodes[index].prev = index;
+                self.nodes[index].next = index;
+                self.hand_hot = Some(index);
+                self.hand_cold = Some(index);
+                self.hand_test = Some(index);
+            },
+            Some(head) => {
+                let prev = self.nodes[head].prev;
+                self.nodes[prev].next = index;
+                self.nodes[index].prev = prev;
+                self.nodes[index].next = head;
+                self.nodes[head].prev = index;
+            },
+        }
+    }
+
+    // 从环上摘下，指向它的指针移到下一个节点
+    fn unlink(&mut self, index:usize){
+        let (prev, next) = (self.nodes[index].prev, self.nodes[index].next);
+        if next == index {
+            self.hand_hot = None;
+            self.hand_cold = None;
+            self.hand_test = None;
+            return;
+        }
+        for hand in [&mut self.hand_hot, &mut self.hand_cold, &mut self.hand_test] {
+            if *hand == Some(index) {
+                *hand = Some(next);
+            }
+        }
+        self.nodes[prev].next = next;
+        self.nodes[next].prev = prev;
+    }
+
+    fn add(&mut self, key:K, val:V, status:Status, expires:Option<Duration>){
+        let node = Node{
+            key:key.clone(),
+            val:Some(val),
+            status,
+            test:status == Status::Cold,
+            referenced:AtomicBool::new(false),
+            expires,
+            prev:0,
+            next:0,
+        };
+        let index = match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = node;
+                i
+            },
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            },
+        };
+        match status {
+            Status::Hot => self.count_hot += 1,
+            Status::Cold => self.count_cold += 1,
+            Status::NonResident => self.count_test += 1,
+        }
+        self.link(index);
+        self.map.insert(key, index);
+    }
+
+    // 从环上彻底删除，返回值
+    fn delete(&mut self, index:usize) -> Option<V>{
+        self.unlink(index);
+        match self.nodes[index].status {
+            Status::Hot => self.count_hot -= 1,
+            Status::Cold => self.count_cold -= 1,
+            Status::NonResident => self.count_test -= 1,
+        }
+        self.map.remove(&self.nodes[index].key);
+        self.free.push(index);
+        self.nodes[index].val.take()
+    }
+
+    // 测试期结束，冷数据区的目标减小
+    fn end_test(&mut self, index:usize){
+        self.nodes[index].test = false;
+        self.cold_target = self.cold_target.saturating_sub(1).max(1);
+    }
+
+    // 删除数据项并把值交给回调，过期的数据项总是以 Expired 通知
+    fn discard(&mut self, index:usize, cause:RemovalCause){
+        let cause = if self.is_expired(index) { RemovalCause::Expired } else { cause };
+        let key = self.nodes[index].key.clone();
+        if let Some(val) = self.delete(index) {
+            if cause == RemovalCause::Capacity || cause == RemovalCause::Expired {
+                self.stats.evicted();
+            }
+            self.notify(key, val, cause);
+        }
+    }
+
+    // 淘汰一个冷数据并返回，调用时至少有一个冷数据。
+    // 过期的冷数据直接删除，以 Expired 返回
+    fn run_hand_cold(&mut self) -> (K, V, RemovalCause){
+        loop {
+            let index = self.hand_cold.unwrap();
+            let next = self.nodes[index].next;
+            if self.nodes[index].status != Status::Cold {
+                self.hand_cold = Some(next);
+                continue;
+            }
+            if self.is_expired(index) {
+                self.hand_cold = Some(next);
+                let key = self.nodes[index].key.clone();
+                let val = self.delete(index);
+                return (key, val.unwrap(), RemovalCause::Expired);
+            }
+            let node = &mut self.nodes[index];
+
+            if std::mem::replace(node.referenced.get_mut(), false) {
+                if node.test {
+                    // 测试期内再次被访问，升级为热数据
+                    node.status = Status::Hot;
+                    node.test = false;
+                    self.count_cold -= 1;
+                    self.count_hot += 1;
+                    self.hand_cold = Some(next);
+                    while self.count_hot > self.hot_target() {
+                        self.run_hand_hot();
+                    }
+                    if self.count_cold == 0 {
+                        self.run_hand_hot();
+                    }
+                } else {
+                    // 重新开始测试期，移到环的头部
+                    node.test = true;
+                    self.unlink(index);
+                    self.link(index);
+                }
+                continue;
+            }
+
+            // 未被访问，淘汰。在测试期内的留下键，继续观察
+            self.hand_cold = Some(next);
+            let key = node.key.clone();
+            let val = if node.test {
+                let val = node.val.take();
+                node.status = Status::NonResident;
+                self.count_cold -= 1;
+                self.count_test += 1;
+                while self.count_test > self.cap {
+                    self.run_hand_test();
+                }
+                val
+            } else {
+                self.delete(index)
+            };
+            return (key, val.unwrap(), RemovalCause::Capacity);
+        }
+    }
+
+    // 把一个未被访问的热数据降级为冷数据，经过的冷数据结束测试期
+    fn run_hand_hot(&mut self){
+        while self.count_hot > 0 {
+            let index = self.hand_hot.unwrap();
+            let next = self.nodes[index].next;
+            let node = &mut self.nodes[index];
+            match node.status {
+                Status::Hot => {
+                    if !std::mem::replace(node.referenced.get_mut(), false) {
+                        node.status = Status::Cold;
+                        self.count_hot -= 1;
+                        self.count_cold += 1;
+                        self.hand_hot = Some(next);
+                        return;
+                    }
+                },
+                Status::NonResident => {
+                    self.end_test(index);
+                    self.delete(index);
+                },
+                Status::Cold => {
+                    if node.test {
+                        self.end_test(index);
+                    }
+                },
+            }
+            self.hand_hot = Some(next);
+        }
+    }
+
+    // 删除一个非驻留的键，经过的冷数据结束测试期
+    fn run_hand_test(&mut self){
+        while self.count_test > 0 {
+            let index = self.hand_test.unwrap();
+            let next = self.nodes[index].next;
+            if self.nodes[index].test {
+                self.end_test(index);
+                if self.nodes[index].status == Status::NonResident {
+                    self.delete(index);
+                    self.hand_test = Some(next);
+                    return;
+                }
+            }
+            self.hand_test = Some(next);
+        }
+    }
+
+    // 淘汰一个数据项并交给回调
+    fn evict(&mut self){
+        if self.count_cold == 0 {
+            self.run_hand_hot();
+        }
+        let (key, val, cause) = self.run_hand_cold();
+        self.stats.evicted();
+        self.notify(key, val, cause);
+    }
+
+    // 为新数据腾出位置
+    fn make_room(&mut self){
+        while self.len() >= self.cap {
+            self.evict();
+        }
+    }
+
+    // 插入，被替换的旧值以 Replaced 交给回调，没有回调时返回旧值
+    pub fn insert(&mut self, key:K, val:V) -> Option<V>{
+        let ttl = self.ttl;
+        self.insert_entry(key, val, ttl, self.replaced())
+    }
+
+    // 插入并单独指定存活时间
+    pub fn insert_with_ttl(&mut self, key:K, val:V, ttl:Duration) -> Option<V>{
+        self.insert_entry(key, val, Some(ttl), self.replaced())
+    }
+
+    // 插入并返回被替换的旧值，旧值不交给回调
+    pub fn insert_silent(&mut self, key:K, val:V) -> Option<V>{
+        let ttl = self.ttl;
+        self.insert_entry(key, val, ttl, None)
+    }
+
+    // 插入，被替换的旧值交给回调而不是返回
+    pub fn put(&mut self, key:K, val:V){
+        let ttl = self.ttl;
+        self.insert_entry(key, val, ttl, Some(RemovalCause::Replaced));
+    }
+
+    // 有回调时被替换的旧值交给回调
+    fn replaced(&self) -> Option<RemovalCause>{
+        self.listener.as_ref().map(|_| RemovalCause::Replaced)
+    }
+
+    // replaced 为 Some 时旧值交给回调，否则返回给调用者
+    fn insert_entry(&mut self, key:K, val:V, ttl:Option<Duration>,
+        replaced:Option<RemovalCause>) -> Option<V>{
+        let expires = ttl.map(|t| self.clock.now() + t);
+
+        if let Some(&index) = self.map.get(&key) {
+            if self.nodes[index].status != Status::NonResident {
+                // 原地替换并设置引用位，原来的值已过期时当作不存在
+                let expired = self.is_expired(index);
+                if expired {
+                    self.stats.evicted();
+                    self.stats.inserted();
+                } else {
+                    self.stats.updated();
+                }
+                let node = &mut self.nodes[index];
+                *node.referenced.get_mut() = true;
+                node.expires = expires;
+                let old_val = node.val.replace(val).unwrap();
+                match (expired, replaced) {
+                    (false, None) => return Some(old_val),
+                    (false, Some(cause)) => self.notify(key, old_val, cause),
+                    (true, _) => self.notify(key, old_val, RemovalCause::Expired),
+                }
+                return None;
+            }
+
+            // 非驻留的键在测试期内再次访问：冷数据区太小，加大目标，直接作为热数据进入
+            self.stats.inserted();
+            self.cold_target = (self.cold_target + 1).min(self.cap);
+            self.delete(index);
+            self.make_room();
+            self.add(key, val, Status::Hot, expires);
+            while self.count_hot > self.hot_target() {
+                self.run_hand_hot();
+            }
+            return None;
+        }
+
+        self.stats.inserted();
+        if self.cap == 0 {
+            self.stats.evicted();
+            self.notify(key, val, RemovalCause::Capacity);
+            return None;
+        }
+        self.make_room();
+        self.add(key, val, Status::Cold, expires);
+        None
+    }
+
+    // 查找驻留且未过期的数据项，记录命中和未命中
+    fn lookup(&self, key:&K) -> Option<usize>{
+        match self.map.get(key) {
+            Some(&index) if self.nodes[index].val.is_some() && !self.is_expired(index) => {
+                self.stats.hit();
+                Some(index)
+            },
+            _ => {
+                self.stats.miss();
+                None
+            },
+        }
+    }
+
+    // 读取数据项并设置引用位
+    pub fn get(&self, key:&K) -> Option<&V>{
+        let node = &self.nodes[self.lookup(key)?];
+        node.referenced.store(true, Ordering::Relaxed);
+        node.val.as_ref()
+    }
+
+    pub fn get_mut(&mut self, key:&K) -> Option<&mut V>{
+        let index = self.lookup(key)?;
+        let node = &mut self.nodes[index];
+        *node.referenced.get_mut() = true;
+        node.val.as_mut()
+    }
+
+    // 键不存在时用 f 计算值并插入，返回缓存中的值；容量为 0 时不缓存，返回计算结果
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key:K, f:F) -> Fetched<'_, V>{
+        match self.try_get_or_insert_with(key, || Ok::<V, ()>(f())) {
+            Ok(v) => v,
+            Err(()) => unreachable!(),
+        }
+    }
+
+    // 同 get_or_insert_with，计算失败时不插入并返回错误
+    pub fn try_get_or_insert_with<E, F>(&mut self, key:K, f:F) -> Result<Fetched<'_, V>, E>
+        where F: FnOnce() -> Result<V, E> {
+        let index = match self.lookup(&key) {
+            Some(index) => {
+                *self.nodes[index].referenced.get_mut() = true;
+                index
+            },
+            None if self.cap == 0 => {
+                return Ok(Fetched::Computed(f()?));
+            },
+            None => {
+                self.insert(key.clone(), f()?);
+                self.map[&key]
+            },
+        };
+        Ok(Fetched::Cached(self.nodes[index].val.as_ref().unwrap()))
+    }
+
+    // 读取数据项，不设置引用位
+    pub fn peek(&self, key:&K) -> Option<&V>{
+        let index = *self.map.get(key)?;
+        if self.is_expired(index) {
+            return None;
+        }
+        self.nodes[index].val.as_ref()
+    }
+
+    // 下一个淘汰的候选：从 hand_cold 起第一个未被访问的冷数据，不转动指针。
+    // 冷数据都被访问过时，pop_lru 要先升级、降级数据项，这时返回 hand_cold 之后的第一个冷数据，
+    // 没有冷数据时返回 hand_hot 之后的第一个热数据，只是近似
+    pub fn peek_lru(&self) -> Option<(&K, &V)>{
+        let walk = |start:Option<usize>| (0..self.nodes.len())
+            .scan(start, move |cur, _| {
+                let index = (*cur)?;
+                *cur = Some(self.nodes[index].next);
+                Some(&self.nodes[index])
+            });
+        let now = self.clock.now();
+        let live = move |n:&&Node<K, V>| n.expires.map_or(true, |t| now < t);
+        let node = walk(self.hand_cold)
+            .filter(|n| n.status == Status::Cold)
+            .filter(live)
+            .find(|n| !n.referenced.load(Ordering::Relaxed))
+            .or_else(|| walk(self.hand_cold).filter(live).find(|n| n.status == Status::Cold))
+            .or_else(|| walk(self.hand_hot).filter(live).find(|n| n.status == Status::Hot))?;
+        node.val.as_ref().map(|v| (&node.key, v))
+    }
+
+    // 按 CLOCK-Pro 策略淘汰一个未过期的数据项并返回，测试期内的冷数据留下非驻留的键。
+    // 途经的过期数据项一并删除
+    pub fn pop_lru(&mut self) -> Option<(K, V)>{
+        while !self.is_empty() {
+            if self.count_cold == 0 {
+                self.run_hand_hot();
+            }
+            let (key, val, cause) = self.run_hand_cold();
+            if cause != RemovalCause::Expired {
+                return Some((key, val));
+            }
+            self.stats.evicted();
+            self.notify(key, val, cause);
+        }
+        None
+    }
+
+    // 删除，值以 Explicit 交给回调，没有回调时返回值。非驻留的键一起删除
+    pub fn remove(&mut self, key:&K) -> Option<V>{
+        if self.listener.is_some() {
+            self.invalidate(key);
+            return None;
+        }
+        self.remove_silent(key)
+    }
+
+    // 删除并返回值，值不交给回调
+    pub fn remove_silent(&mut self, key:&K) -> Option<V>{
+        let index = *self.map.get(key)?;
+        if self.is_expired(index) {
+            self.discard(index, RemovalCause::Expired);
+            return None;
+        }
+        self.delete(index)
+    }
+
+    // 主动删除，值交给回调，返回键是否存在
+    pub fn invalidate(&mut self, key:&K) -> bool{
+        match self.map.get(key) {
+            Some(&index) => {
+                let live = self.nodes[index].val.is_some() && !self.is_expired(index);
+                self.discard(index, RemovalCause::Explicit);
+                live
+            },
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, key:&K) -> bool{
+        self.peek(key).is_some()
+    }
+
+    // 清理所有过期的数据项，返回清理的数量
+    pub fn purge_expired(&mut self) -> usize{
+        let expired: Vec<_> = self.ring().into_iter()
+            .filter(|&index| self.is_expired(index))
+            .collect();
+        for &index in expired.iter() {
+            self.discard(index, RemovalCause::Expired);
+        }
+        expired.len()
+    }
+
+    // 调整容量，缩小时按 CLOCK-Pro 策略淘汰
+    pub fn resize(&mut self, cap:usize){
+        if cap == 0 {
+            for index in self.ring() {
+                self.discard(index, RemovalCause::Capacity);
+            }
+            self.clear();
+        }
+        self.cap = cap;
+        self.cold_target = self.cold_target.min(cap).max(cap.min(1));
+        while self.len() > cap {
+            self.evict();
+        }
+        while self.count_hot > self.hot_target() {
+            self.run_hand_hot();
+        }
+        while self.count_test > cap {
+            self.run_hand_test();
+        }
+    }
+
+    // 清空缓存，有回调时所有值都交给回调
+    pub fn clear(&mut self){
+        if self.listener.is_some() {
+            for index in self.ring() {
+                self.discard(index, RemovalCause::Explicit);
+            }
+        }
+        self.cold_target = self.cap;
+        self.map.clear();
+        self.nodes.clear();
+        self.free.clear();
+        self.hand_hot = None;
+        self.hand_cold = None;
+        self.hand_test = None;
+        self.count_hot = 0;
+        self.count_cold = 0;
+        self.count_test = 0;
+    }
+
+    // 取出全部未过期的驻留数据项，从环的头部开始排列，过期的交给回调，非驻留的键一起清除
+    pub fn drain(&mut self) -> std::vec::IntoIter<(K, V)>{
+        let mut items = Vec::with_capacity(self.len());
+        for index in self.ring() {
+            let expired = self.is_expired(index);
+            let key = self.nodes[index].key.clone();
+            match self.nodes[index].val.take() {
+                Some(v) if expired => {
+                    self.stats.evicted();
+                    self.notify(key, v, RemovalCause::Expired);
+                },
+                Some(v) => items.push((key, v)),
+                None => {},
+            }
+        }
+        self.clear();
+        items.into_iter()
+    }
+
+    // 从环的头部开始遍历未过期的驻留数据项
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)>{
+        let now = self.clock.now();
+        (0..self.map.len())
+            .scan(self.hand_hot, move |cur, _| {
+                let index = (*cur)?;
+                *cur = Some(self.nodes[index].next);
+                Some(&self.nodes[index])
+            })
+            .filter(move |node| node.expires.map_or(true, |t| now < t))
+            .filter_map(|node| node.val.as_ref().map(|v| (&node.key, v)))
+    }
+}
+
+impl<K: Clone + Hash + Eq, V> Default for CLOCKPro_Cache<K, V> {
+    fn default() -> Self{
+        Self::new()
+    }
+}
+
+impl<K: Clone + Hash + Eq, V> CachePolicy<K, V> for CLOCKPro_Cache<K, V> {
+    fn insert(&mut self, key:K, val:V) -> Option<V>{
+        CLOCKPro_Cache::insert(self, key, val)
+    }
+
+    fn get(&mut self, key:&K) -> Option<&V>{
+        CLOCKPro_Cache::get(self, key)
+    }
+
+    fn peek(&self, key:&K) -> Option<&V>{
+        CLOCKPro_Cache::peek(self, key)
+    }
+
+    fn remove(&mut self, key:&K) -> Option<V>{
+        CLOCKPro_Cache::remove(self, key)
+    }
+
+    fn contains(&self, key:&K) -> bool{
+        CLOCKPro_Cache::contains(self, key)
+    }
+
+    fn len(&self) -> usize{
+        CLOCKPro_Cache::len(self)
+    }
+
+    fn cap(&self) -> usize{
+        CLOCKPro_Cache::cap(self)
+    }
+
+    fn clear(&mut self){
+        CLOCKPro_Cache::clear(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::LRU::lru::{LRU_Cache, ManualClock};
+    use crate::LRU::policy::hit_ratio;
+
+    // 检查计数和环的一致性
+    fn check<K: Clone + Hash + Eq, V>(cache:&CLOCKPro_Cache<K, V>) {
+        let nodes: Vec<_> = cache.map.values().map(|&i| &cache.nodes[i]).collect();
+        let count = |s| nodes.iter().filter(|n| n.status == s).count();
+        assert_eq!(count(Status::Hot), cache.count_hot);
+        assert_eq!(count(Status::Cold), cache.count_cold);
+        assert_eq!(count(Status::NonResident), cache.count_test);
+        assert!(nodes.iter().all(|n| (n.status == Status::NonResident) == n.val.is_none()));
+        assert!(cache.len() <= cache.cap && cache.count_test <= cache.cap);
+        assert_eq!(cache.iter().count(), cache.len());
+    }
+
+    #[test]
+    fn it_works() {
+        let mut cache = CLOCKPro_Cache::with_capacity(2);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        let shared = &cache;
+        assert_eq!(shared.get(&1), Some(&"a"));
+
+        // 1 在测试期内被访问过，2 被淘汰，只留下键
+        cache.insert(3, "c");
+        assert!(cache.contains(&1) && !cache.contains(&2));
+        assert_eq!(cache.count_test, 1);
+        check(&cache);
+
+        // 2 在测试期内再次访问，重新进入缓存
+        cache.insert(2, "b");
+        assert!(cache.contains(&2));
+        assert_eq!(cache.peek(&2), Some(&"b"));
+        assert_eq!(cache.insert(2, "bb"), Some("b"));
+        check(&cache);
+
+        assert_eq!(cache.remove(&2), Some("bb"));
+        check(&cache);
+        cache.resize(1);
+        assert_eq!(cache.len(), 1);
+        check(&cache);
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_pop_drain() {
+        let mut cache = CLOCKPro_Cache::with_capacity(3);
+        assert_eq!(cache.peek_lru(), None);
+        assert_eq!(cache.pop_lru(), None);
+        for i in 0..3 {
+            cache.insert(i, i * 10);
+        }
+        cache.get(&0);
+
+        // 没被访问过的冷数据先淘汰，peek_lru 和 pop_lru 一致
+        let (&k, &v) = cache.peek_lru().unwrap();
+        assert_eq!(cache.pop_lru(), Some((k, v)));
+        assert!(!cache.contains(&k) && k != 0);
+        check(&cache);
+
+        while let Some((k, _)) = cache.peek_lru().map(|(k, v)| (*k, *v)) {
+            assert_eq!(cache.pop_lru().map(|(k, _)| k), Some(k));
+            check(&cache);
+        }
+        assert!(cache.is_empty());
+
+        for i in 0..5 {
+            cache.insert(i, i * 10);
+        }
+        let mut items: Vec<_> = cache.drain().collect();
+        items.sort();
+        assert_eq!(items.len(), 3);
+        assert!(items.iter().all(|&(k, v)| v == k * 10));
+        assert!(cache.is_empty() && cache.map.is_empty());
+        check(&cache);
+    }
+
+    #[test]
+    fn test_churn_bounded() {
+        let mut cache = CLOCKPro_Cache::with_capacity(16);
+        for i in 0..20_000u32 {
+            let key = (i * 7919) % 37 + if i % 3 == 0 { 0 } else { 100 + i % 11 };
+            if cache.get(&key).is_none() {
+                cache.insert(key, i);
+            }
+            if i % 17 == 0 {
+                cache.remove(&(i % 50));
+            }
+            if i % 5000 == 0 {
+                cache.resize(16 - (i / 5000) as usize * 4 + 1);
+            }
+            check(&cache);
+        }
+    }
+
+    #[test]
+    fn test_scan_resistance() {
+        // 和 policy 中相同的访问记录：热点被一次性的顺序扫描打断
+        let mut trace = Vec::new();
+        for round in 0..20 {
+            for _ in 0..5 {
+                trace.extend(0..8);
+            }
+            trace.extend((0..30).map(|i| 1000 + round * 30 + i));
+        }
+        let lru = hit_ratio(&mut LRU_Cache::with_capacity(10), &trace);
+        let pro = hit_ratio(&mut CLOCKPro_Cache::with_capacity(10), &trace);
+        assert!(pro > lru, "clock-pro {} lru {}", pro, lru);
+    }
+
+    #[test]
+    fn test_ttl() {
+        let clock = Arc::new(ManualClock::new());
+        let mut cache = CLOCKPro_Cache::with_ttl(3, Duration::from_secs(10));
+        cache.set_clock(clock.clone());
+
+        cache.insert("a", 1);
+        cache.insert_with_ttl("b", 2, Duration::from_secs(30));
+        clock.advance(Duration::from_secs(5));
+        cache.insert("c", 3);
+        assert_eq!(cache.get(&"a"), Some(&1));
+
+        // a 过期后读不到；它是冷数据，淘汰时直接删除，不留非驻留的键
+        clock.advance(Duration::from_secs(6));
+        assert_eq!(cache.get(&"a"), None);
+        assert!(!cache.contains(&"a"));
+        assert_eq!(cache.iter().count(), 2);
+        assert_eq!(cache.peek_lru(), Some((&"b", &2)));
+        assert_eq!(cache.pop_lru(), Some(("b", 2)));
+        assert!(!cache.map.contains_key(&"a"));
+        check(&cache);
+
+        cache.insert("d", 4);
+        clock.advance(Duration::from_secs(10));
+        cache.insert_with_ttl("e", 5, Duration::from_secs(30));
+        assert_eq!(cache.purge_expired(), 2);
+        assert_eq!(cache.drain().collect::<Vec<_>>(), vec![("e", 5)]);
+        check(&cache);
+    }
+
+    #[test]
+    fn test_eviction_listener() {
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let clock = Arc::new(ManualClock::new());
+        let mut cache = CLOCKPro_Cache::with_capacity(2);
+        cache.set_clock(clock.clone());
+        let log = removed.clone();
+        cache.set_eviction_listener(move |k, v, cause| log.lock().unwrap().push((k, v, cause)));
+
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.insert("c", 3);
+        cache.put("b", 20);
+        assert_eq!(cache.insert("b", 200), None);
+        assert!(cache.invalidate(&"c"));
+        // a 只剩非驻留的键，删除它不通知
+        assert!(!cache.invalidate(&"a"));
+        cache.insert_with_ttl("d", 4, Duration::from_secs(1));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(cache.get(&"d"), None);
+        assert_eq!(cache.remove(&"d"), None);
+        assert_eq!(cache.remove(&"b"), None);
+        cache.insert("e", 5);
+        assert_eq!(cache.insert_silent("e", 50), Some(5));
+        cache.clear();
+
+        assert_eq!(*removed.lock().unwrap(), vec![
+            ("a", 1, RemovalCause::Capacity),
+            ("b", 2, RemovalCause::Replaced),
+            ("b", 20, RemovalCause::Replaced),
+            ("c", 3, RemovalCause::Explicit),
+            ("d", 4, RemovalCause::Expired),
+            ("b", 200, RemovalCause::Explicit),
+            ("e", 50, RemovalCause::Explicit),
+        ]);
+    }
+
+    #[test]
+    fn test_stats() {
+        let clock = Arc::new(ManualClock::new());
+        let mut cache = CLOCKPro_Cache::with_capacity(2);
+        cache.set_clock(clock.clone());
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        cache.insert(2, 20);
+        cache.insert(3, 3);
+        cache.get(&3);
+        cache.get(&1);
+        cache.peek(&2);
+        cache.insert_with_ttl(4, 4, Duration::from_secs(1));
+        clock.advance(Duration::from_secs(1));
+        cache.get(&4);
+
+        assert_eq!(cache.stats(), CacheStats{
+            hits:1, misses:2, inserts:4, updates:1, evictions:2,
+        });
+        cache.reset_stats();
+        assert_eq!(cache.stats(), CacheStats::default());
+    }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        let mut cache = CLOCKPro_Cache::with_capacity(2);
+        assert_eq!(cache.get_or_insert_with(1, || 10), Fetched::Cached(&10));
+        assert_eq!(*cache.get_or_insert_with(1, || 11), 10);
+        assert_eq!(cache.try_get_or_insert_with(2, || Err::<i32, _>("failed")), Err("failed"));
+        assert!(!cache.contains(&2));
+        assert_eq!(cache.stats().hits, 1);
+
+        // 容量为 0 时不缓存，直接返回计算结果
+        let mut cache = CLOCKPro_Cache::with_capacity(0);
+        assert_eq!(cache.get_or_insert_with(1, || 10), Fetched::Computed(10));
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,382 @@
+// LFU 缓存，淘汰访问次数最少的数据项，次数相同时淘汰其中最久未使用的
+// 访问次数相同的数据项放在同一个双向链表（频率桶）中，非空的频率桶再按次数从小到大
+// 连成双向链表，头部就是次数最少的桶。访问时把数据项移到下一个桶（次数 + 1，没有就新建），
+// 桶空了就从链表中摘下，插入、访问、删除和淘汰都是 O(1)
+
+use std::hash::Hash;
+use std::collections::HashMap;
+use crate::LRU::policy::CachePolicy;
+
+// 数据项
+struct Node<K, V> {
+    key:K,
+    val:Option<V>,
+    bucket:usize,               // 所在的频率桶
+    prev:Option<usize>,
+    next:Option<usize>,
+}
+
+// 频率桶
+struct Bucket {
+    freq:usize,
+    head:Option<usize>,         // 桶内最近使用的数据项
+    tail:Option<usize>,         // 桶内最久未使用的数据项
+    prev:Option<usize>,         // 次数更少的桶
+    next:Option<usize>,         // 次数更多的桶
+}
+
+#[allow(non_camel_case_types)]
+pub struct LFU_Cache<K, V> {
+    cap:usize,
+    map:HashMap<K, usize>,
+    nodes:Vec<Node<K, V>>,
+    free:Vec<usize>,
+    buckets:Vec<Bucket>,
+    free_buckets:Vec<usize>,
+    min_bucket:Option<usize>,   // 次数最少的桶，即桶链表的头部
+}
+
+impl<K: Clone + Hash + Eq, V> LFU_Cache<K, V> {
+    pub fn with_capacity(cap:usize) -> Self{
+        LFU_Cache{
+            cap,
+            map:HashMap::with_capacity(cap),
+            nodes:Vec::with_capacity(cap),
+            free:Vec::new(),
+            buckets:Vec::new(),
+            free_buckets:Vec::new(),
+            min_bucket:None,
+        }
+    }
+
+    pub fn len(&self) -> usize{
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.map.is_empty()
+    }
+
+    pub fn cap(&self) -> usize{
+        self.cap
+    }
+
+    // 数据项的访问次数
+    pub fn frequency(&self, key:&K) -> Option<usize>{
+        self.map.get(key).map(|&i| self.buckets[self.nodes[i].bucket].freq)
+    }
+
+    // 最少的访问次数
+    pub fn min_frequency(&self) -> Option<usize>{
+        self.min_bucket.map(|b| self.buckets[b].freq)
+    }
+
+    // 新建次数为 freq 的空桶，放在 prev 之后，prev 为 None 时放在链表头部
+    fn new_bucket(&mut self, freq:usize, prev:Option<usize>) -> usize{
+        let next = match prev {
+            Some(p) => self.buckets[p].next,
+            None => self.min_bucket,
+        };
+        let bucket = Bucket{ freq, head:None, tail:None, prev, next };
+        let b = match self.free_buckets.pop() {
+            Some(b) => {
+                self.buckets[b] = bucket;
+                b
+            },
+            None => {
+                self.buckets.push(bucket);
+                self.buckets.len() - 1
+            },
+        };
+        match prev {
+            Some(p) => self.buckets[p].next = Some(b),
+            None => self.min_bucket = Some(b),
+        }
+        if let Some(n) = next {
+            self.buckets[n].prev = Some(b);
+        }
+        b
+    }
+
+    // 从链表中摘下空桶
+    fn remove_bucket(&mut self, b:usize){
+        let (prev, next) = (self.buckets[b].prev, self.buckets[b].next);
+        match prev {
+            Some(p) => self.buckets[p].next = next,
+            None => self.min_bucket = next,
+        }
+        if let Some(n) = next {
+            self.buckets[n].prev = prev;
+        }
+        self.free_buckets.push(b);
+    }
+
+    // 从所在的频率桶中摘下，桶空了就删除
+    fn unlink(&mut self, index:usize){
+        let (b, prev, next) = {
+            let node = &mut self.nodes[index];
+            (node.bucket, node.prev.take(), node.next.take())
+        };
+        match prev {
+            Some(p) => self.nodes[p].next = next,
+            None => self.buckets[b].head = next,
+        }
+        match next {
+            Some(n) => self.nodes[n].prev = prev,
+            None => self.buckets[b].tail = prev,
+        }
+        if self.buckets[b].head.is_none() {
+            self.remove_bucket(b);
+        }
+    }
+
+    // 放到频率桶的头部
+    fn push_front(&mut self, index:usize, b:usize){
+        let head = self.buckets[b].head;
+        self.nodes[index].bucket = b;
+        self.nodes[index].next = head;
+        match head {
+            Some(h) => self.nodes[h].prev = Some(index),
+            None => self.buckets[b].tail = Some(index),
+        }
+        self.buckets[b].head = Some(index);
+    }
+
+    // 记录一次访问，移到次数 + 1 的桶。先找到目标桶再摘下，原来的桶空了也不影响目标桶的位置
+    fn touch(&mut self, index:usize){
+        let b = self.nodes[index].bucket;
+        let freq = self.buckets[b].freq;
+        let target = match self.buckets[b].next {
+            Some(n) if self.buckets[n].freq == freq + 1 => n,
+            _ => self.new_bucket(freq + 1, Some(b)),
+        };
+        self.unlink(index);
+        self.push_front(index, target);
+    }
+
+    fn remove_index(&mut self, index:usize) -> Option<V>{
+        self.unlink(index);
+        self.map.remove(&self.nodes[index].key);
+        self.free.push(index);
+        self.nodes[index].val.take()
+    }
+
+    // 移除并返回访问次数最少的数据项
+    pub fn pop_lfu(&mut self) -> Option<(K, V)>{
+        let tail = self.buckets[self.min_bucket?].tail?;
+        let key = self.nodes[tail].key.clone();
+        self.remove_index(tail).map(|v| (key, v))
+    }
+
+    pub fn insert(&mut self, key:K, val:V) -> Option<V>{
+        if let Some(&index) = self.map.get(&key) {
+            self.touch(index);
+            return self.nodes[index].val.replace(val);
+        }
+        if self.cap == 0 {
+            return None;
+        }
+        if self.len() >= self.cap {
+            self.pop_lfu();
+        }
+
+        // 新数据项访问次数为 1，放在链表头部的桶
+        let b = match self.min_bucket {
+            Some(b) if self.buckets[b].freq == 1 => b,
+            _ => self.new_bucket(1, None),
+        };
+        let node = Node{ key:key.clone(), val:Some(val), bucket:b, prev:None, next:None };
+        let index = match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = node;
+                i
+            },
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            },
+        };
+        self.push_front(index, b);
+        self.map.insert(key, index);
+        None
+    }
+
+    pub fn get(&mut self, key:&K) -> Option<&V>{
+        let index = *self.map.get(key)?;
+        self.touch(index);
+        self.nodes[index].val.as_ref()
+    }
+
+    pub fn peek(&self, key:&K) -> Option<&V>{
+        let index = *self.map.get(key)?;
+        self.nodes[index].val.as_ref()
+    }
+
+    pub fn remove(&mut self, key:&K) -> Option<V>{
+        let index = *self.map.get(key)?;
+        self.remove_index(index)
+    }
+
+    pub fn contains(&self, key:&K) -> bool{
+        self.map.contains_key(key)
+    }
+
+    pub fn clear(&mut self){
+        self.map.clear();
+        self.nodes.clear();
+        self.free.clear();
+        self.buckets.clear();
+        self.free_buckets.clear();
+        self.min_bucket = None;
+    }
+}
+
+impl<K: Clone + Hash + Eq, V> CachePolicy<K, V> for LFU_Cache<K, V> {
+    fn insert(&mut self, key:K, val:V) -> Option<V>{
+        LFU_Cache::insert(self, key, val)
+    }
+
+    fn get(&mut self, key:&K) -> Option<&V>{
+        LFU_Cache::get(self, key)
+    }
+
+    fn peek(&self, key:&K) -> Option<&V>{
+        LFU_Cache::peek(self, key)
+    }
+
+    fn remove(&mut self, key:&K) -> Option<V>{
+        LFU_Cache::remove(self, key)
+    }
+
+    fn contains(&self, key:&K) -> bool{
+        LFU_Cache::contains(self, key)
+    }
+
+    fn len(&self) -> usize{
+        LFU_Cache::len(self)
+    }
+
+    fn cap(&self) -> usize{
+        LFU_Cache::cap(self)
+    }
+
+    fn clear(&mut self){
+        LFU_Cache::clear(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn it_works() {
+        let mut cache = LFU_Cache::with_capacity(3);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.insert("c", 3);
+        cache.get(&"a");
+        cache.get(&"a");
+        cache.get(&"b");
+        assert_eq!(cache.frequency(&"a"), Some(3));
+
+        // c 次数最少被淘汰，之后 d 与 b 中 d 次数更少
+        cache.insert("d", 4);
+        assert!(!cache.contains(&"c"));
+        cache.insert("e", 5);
+        assert!(!cache.contains(&"d"));
+
+        // 次数相同时淘汰最久未使用的
+        cache.get(&"e");
+        cache.insert("f", 6);
+        assert!(!cache.contains(&"b"));
+        assert_eq!(cache.pop_lfu(), Some(("f", 6)));
+
+        assert_eq!(cache.remove(&"e"), Some(5));
+        assert_eq!(cache.pop_lfu(), Some(("a", 1)));
+        assert!(cache.is_empty());
+        assert_eq!(cache.pop_lfu(), None);
+    }
+
+    // 检查桶链表按次数递增、桶都非空，且数据项都在所属的桶里
+    fn check<K: Clone + Hash + Eq, V>(cache:&LFU_Cache<K, V>) {
+        let (mut cur, mut prev, mut count) = (cache.min_bucket, None, 0);
+        while let Some(b) = cur {
+            let bucket = &cache.buckets[b];
+            assert_eq!(bucket.prev, prev);
+            assert!(bucket.head.is_some());
+            if let Some(p) = prev {
+                assert!(cache.buckets[p].freq < bucket.freq);
+            }
+            let mut node = bucket.head;
+            while let Some(i) = node {
+                assert_eq!(cache.nodes[i].bucket, b);
+                count += 1;
+                node = cache.nodes[i].next;
+            }
+            prev = cur;
+            cur = bucket.next;
+        }
+        assert_eq!(count, cache.len());
+    }
+
+    #[test]
+    fn test_min_freq() {
+        let mut cache = LFU_Cache::with_capacity(3);
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        cache.get(&1);
+        cache.get(&2);
+        cache.get(&2);
+
+        // 访问使最小频率桶变空，最少次数变为下一个桶的次数
+        assert_eq!(cache.min_frequency(), Some(2));
+        check(&cache);
+
+        // 删空最小频率桶后立即得到下一个桶
+        assert_eq!(cache.remove(&1), Some(1));
+        assert_eq!(cache.min_frequency(), Some(3));
+        cache.get(&2);
+        assert_eq!(cache.pop_lfu(), Some((2, 2)));
+        assert_eq!(cache.min_frequency(), None);
+        assert_eq!(cache.pop_lfu(), None);
+        check(&cache);
+
+        // 插入时淘汰后最少次数为 1
+        cache.insert(3, 3);
+        cache.insert(4, 4);
+        cache.insert(5, 5);
+        for _ in 0..3 {
+            cache.get(&3);
+            cache.get(&4);
+        }
+        cache.remove(&5);
+        cache.insert(6, 6);
+        cache.get(&6);
+        cache.insert(7, 7);
+        assert!(!cache.contains(&6));
+        assert_eq!(cache.min_frequency(), Some(1));
+        check(&cache);
+        assert_eq!(cache.pop_lfu(), Some((7, 7)));
+        assert_eq!(cache.min_frequency(), Some(4));
+        assert_eq!(cache.pop_lfu(), Some((3, 3)));
+    }
+
+    #[test]
+    fn test_churn() {
+        let mut cache = LFU_Cache::with_capacity(8);
+        for i in 0..5000u32 {
+            let key = (i * 7919) % 23;
+            if cache.get(&key).is_none() {
+                cache.insert(key, i);
+            }
+            if i % 11 == 0 {
+                cache.remove(&(i % 23));
+            }
+            if i % 13 == 0 {
+                cache.pop_lfu();
+            }
+            assert!(cache.len() <= 8);
+            check(&cache);
+        }
+    }
+}
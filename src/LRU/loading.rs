@@ -0,0 +1,158 @@
+// 自动加载的缓存
+// 未命中时调用 Loader 加载数据并放入缓存，调用方不用再重复“查找-未命中-计算-插入”，
+// 批量读取时所有未命中的键一次性交给 load_all
+
+use std::hash::Hash;
+use std::collections::HashMap;
+use crate::LRU::lru::{LRU_Cache, Fetched};
+
+// 数据加载器
+pub trait Loader<K, V> {
+    type Error;
+
+    fn load(&self, key:&K) -> Result<V, Self::Error>;
+
+    // 批量加载，默认逐个调用 load，可以覆盖成一次批量请求
+    fn load_all(&self, keys:&[K]) -> Result<Vec<(K, V)>, Self::Error>
+        where K: Clone {
+        keys.iter().map(|k| Ok((k.clone(), self.load(k)?))).collect()
+    }
+}
+
+// 闭包也可以作为加载器
+impl<K, V, E, F: Fn(&K) -> Result<V, E>> Loader<K, V> for F {
+    type Error = E;
+
+    fn load(&self, key:&K) -> Result<V, E>{
+        self(key)
+    }
+}
+
+pub struct LoadingCache<K, V, L> {
+    cache:LRU_Cache<K, V>,
+    loader:L,
+}
+
+impl<K: Clone + Hash + Eq, V, L: Loader<K, V>> LoadingCache<K, V, L> {
+    pub fn new(cap:usize, loader:L) -> Self{
+        LoadingCache{ cache:LRU_Cache::with_capacity(cap), loader }
+    }
+
+    // 读取数据，未命中时加载，加载失败不缓存；容量为 0 时返回加载结果
+    pub fn get(&mut self, key:&K) -> Result<Fetched<'_, V>, L::Error>{
+        let loader = &self.loader;
+        self.cache.try_get_or_insert_with(key.clone(), || loader.load(key))
+    }
+
+    // 已缓存的数据直接使用，其余的一次性批量加载
+    pub fn get_all(&mut self, keys:&[K]) -> Result<HashMap<K, V>, L::Error>
+        where V: Clone {
+        let mut found = HashMap::with_capacity(keys.len());
+        let mut missing = Vec::new();
+        for key in keys.iter() {
+            match self.cache.get(key) {
+                Some(v) => { found.insert(key.clone(), v.clone()); },
+                None => {
+                    if !missing.contains(key) {
+                        missing.push(key.clone());
+                    }
+                },
+            }
+        }
+
+        if !missing.is_empty() {
+            for (k, v) in self.loader.load_all(&missing)? {
+                self.cache.insert(k.clone(), v.clone());
+                found.insert(k, v);
+            }
+        }
+        Ok(found)
+    }
+
+    // 重新加载，成功后替换缓存中的值
+    pub fn refresh(&mut self, key:&K) -> Result<Fetched<'_, V>, L::Error>{
+        let val = self.loader.load(key)?;
+        if self.cache.cap() == 0 {
+            return Ok(Fetched::Computed(val));
+        }
+        self.cache.insert(key.clone(), val);
+        Ok(Fetched::Cached(self.cache.peek(key).unwrap()))
+    }
+
+    pub fn invalidate(&mut self, key:&K) -> Option<V>{
+        self.cache.remove(key)
+    }
+
+    // 底层的 LRU 缓存
+    pub fn cache(&self) -> &LRU_Cache<K, V>{
+        &self.cache
+    }
+
+    pub fn cache_mut(&mut self) -> &mut LRU_Cache<K, V>{
+        &mut self.cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    // 记录每次调用的加载器
+    struct Db {
+        calls:RefCell<Vec<Vec<u32>>>,
+    }
+
+    impl Loader<u32, String> for Db {
+        type Error = String;
+
+        fn load(&self, key:&u32) -> Result<String, String>{
+            self.load_all(&[*key]).map(|mut v| v.remove(0).1)
+        }
+
+        fn load_all(&self, keys:&[u32]) -> Result<Vec<(u32, String)>, String>{
+            self.calls.borrow_mut().push(keys.to_vec());
+            if keys.contains(&0) {
+                return Err("key 0 not found".to_string());
+            }
+            Ok(keys.iter().map(|k| (*k, format!("v{}", k))).collect())
+        }
+    }
+
+    #[test]
+    fn it_works() {
+        let mut cache = LoadingCache::new(10, Db{ calls:RefCell::new(Vec::new()) });
+        assert_eq!(*cache.get(&1).unwrap(), "v1");
+        assert_eq!(*cache.get(&1).unwrap(), "v1");
+        assert_eq!(cache.get(&0), Err("key 0 not found".to_string()));
+
+        let all = cache.get_all(&[1, 2, 3, 2]).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[&3], "v3");
+        assert_eq!(*cache.loader.calls.borrow(), vec![vec![1], vec![0], vec![2, 3]]);
+
+        assert_eq!(cache.invalidate(&2), Some("v2".to_string()));
+        assert_eq!(*cache.refresh(&2).unwrap(), "v2");
+        assert_eq!(cache.cache().len(), 3);
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        // 容量为 0 时每次都加载，不缓存也不 panic
+        let mut cache = LoadingCache::new(0, Db{ calls:RefCell::new(Vec::new()) });
+        assert_eq!(cache.get(&1), Ok(Fetched::Computed("v1".to_string())));
+        assert_eq!(*cache.get(&1).unwrap(), "v1");
+        assert_eq!(*cache.refresh(&2).unwrap(), "v2");
+        assert_eq!(cache.get_all(&[3]).unwrap()[&3], "v3");
+        assert!(cache.cache().is_empty());
+        assert_eq!(cache.loader.calls.borrow().len(), 4);
+    }
+
+    #[test]
+    fn test_closure_loader() {
+        let mut cache = LoadingCache::new(2, |k: &u32| Ok::<u32, ()>(k * 2));
+        assert_eq!(cache.get(&4), Ok(Fetched::Cached(&8)));
+        assert_eq!(cache.get_all(&[1, 4]).unwrap()[&1], 2);
+        assert_eq!(cache.cache().stats().hits, 1);
+    }
+}
@@ -1,167 +1,744 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::ops::Deref;
 use std::hash::Hash;
 use std::collections::HashMap;
+use std::sync::{Arc,Mutex};
+use std::sync::atomic::{AtomicU64,Ordering};
+use std::time::{Duration,Instant};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 
 // 缓存容量
 const CACHE_SIZE:usize = 100;
 
+// 时钟，返回从某个固定起点开始经过的时间，测试时可以换成手动时钟
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Duration;
+}
+
+// 系统时钟，以创建时刻为起点
+pub struct SystemClock {
+    start:Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self{
+        SystemClock{ start:Instant::now() }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration{
+        self.start.elapsed()
+    }
+}
+
+// 手动时钟，只有调用 advance 时才前进
+pub struct ManualClock {
+    now:Mutex<Duration>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self{
+        ManualClock{ now:Mutex::new(Duration::ZERO) }
+    }
+
+    pub fn advance(&self, d:Duration){
+        *self.now.lock().unwrap() += d;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration{
+        *self.now.lock().unwrap()
+    }
+}
+
+// 数据项离开缓存的原因
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum RemovalCause {
+    Capacity,       // 容量不足被淘汰
+    Explicit,       // 调用 remove、invalidate 或 clear 主动删除
+    Replaced,       // 调用 insert 或 put 时被新值替换
+    Expired,        // 过期
+}
+
+// 读写快照的错误
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum SnapshotError {
+    Decode,                     // 快照内容无法解析
+    Io(io::ErrorKind),          // 读写快照文件失败
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e:io::Error) -> Self {
+        SnapshotError::Io(e.kind())
+    }
+}
+
+// 数据项离开缓存时的回调，值被移交给回调
+pub type EvictionListener<K, V> = Box<dyn FnMut(K, V, RemovalCause) + Send>;
+
+// get 只需 &self 的缓存可能被多个线程通过只读引用共享，回调还要求 Sync
+pub(crate) type SyncListener<K, V> = Box<dyn FnMut(K, V, RemovalCause) + Send + Sync>;
+
+// 缓存统计的快照
+#[derive(Debug,Clone,Copy,Default,PartialEq,Eq)]
+pub struct CacheStats {
+    pub hits:u64,
+    pub misses:u64,
+    pub inserts:u64,        // 插入新键
+    pub updates:u64,        // 替换已有键的值
+    pub evictions:u64,      // 因容量不足或过期被淘汰
+}
+
+impl CacheStats {
+    pub fn requests(&self) -> u64{
+        self.hits + self.misses
+    }
+
+    // 命中率，没有请求时为 0
+    pub fn hit_ratio(&self) -> f64{
+        match self.requests() {
+            0 => 0.0,
+            n => self.hits as f64 / n as f64,
+        }
+    }
+}
+
+// 原子计数的统计，给 get 只需 &self 的缓存（CLOCK、CLOCK-Pro）使用
+#[derive(Debug,Default)]
+pub(crate) struct SharedStats {
+    hits:AtomicU64,
+    misses:AtomicU64,
+    inserts:AtomicU64,
+    updates:AtomicU64,
+    evictions:AtomicU64,
+}
+
+impl SharedStats {
+    pub(crate) fn hit(&self){
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn miss(&self){
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn inserted(&self){
+        self.inserts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn updated(&self){
+        self.updates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn evicted(&self){
+        self.evictions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> CacheStats{
+        CacheStats{
+            hits:self.hits.load(Ordering::Relaxed),
+            misses:self.misses.load(Ordering::Relaxed),
+            inserts:self.inserts.load(Ordering::Relaxed),
+            updates:self.updates.load(Ordering::Relaxed),
+            evictions:self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// get_or_insert_with 的结果：缓存中的值，或者容量为 0 时没有缓存、直接交还的计算结果
+#[derive(Debug,PartialEq,Eq)]
+pub enum Fetched<'a, V> {
+    Cached(&'a V),
+    Computed(V),
+}
+
+impl<V> Deref for Fetched<'_, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V{
+        match self {
+            Fetched::Cached(v) => v,
+            Fetched::Computed(v) => v,
+        }
+    }
+}
+
+impl<V: Clone> Fetched<'_, V> {
+    pub fn into_owned(self) -> V{
+        match self {
+            Fetched::Cached(v) => v.clone(),
+            Fetched::Computed(v) => v,
+        }
+    }
+}
+
 // 数据项
 struct Entry<K, V> {
     key:K,
     val:Option<V>,
     next:Option<usize>,
     prev:Option<usize>,
+    expires:Option<Duration>,   // 过期时刻，None 表示永不过期
 }
 
 // LRU 缓存
-struct LRU_Cache<K, V> {
+// entries 中的数据项通过 prev/next 组成双向链表，head 为最近使用，tail 为最久未使用
+// 过期的数据项在访问时当作不存在，len 中包含尚未清理的过期项，可用 purge_expired 回收
+// 设置了 listener 时，被替换、删除、淘汰、过期的值都交给 listener，
+// 值是移交而不是克隆的，所以此时 insert/remove 不再返回旧值。
+// 需要拿回值时用 insert_silent/remove_silent/pop_lru/drain，它们返回的值不会通知 listener
+pub struct LRU_Cache<K, V> {
     cap: usize,
     head:Option<usize>,
     tail:Option<usize>,
     map:HashMap<K, usize>,
     entries:Vec<Entry<K, V>>,
+    free:Vec<usize>,            // 被淘汰或删除后空出来的位置，插入时优先复用
+    ttl:Option<Duration>,       // 默认存活时间
+    clock:Arc<dyn Clock>,
+    listener:Option<EvictionListener<K, V>>,
+    stats:CacheStats,
 }
 
 impl<K: Clone + Hash + Eq, V> LRU_Cache<K, V> {
-    fn new() -> Self{
+    pub fn new() -> Self{
         Self::with_capacity(CACHE_SIZE)
     }
-    
-    fn with_capacity(cap:usize) -> Self{
+
+    pub fn with_capacity(cap:usize) -> Self{
         LRU_Cache {
             cap:cap,
             head:None,
             tail:None,
             map:HashMap::with_capacity(cap),
             entries:Vec::with_capacity(cap),
+            free:Vec::new(),
+            ttl:None,
+            clock:Arc::new(SystemClock::new()),
+            listener:None,
+            stats:CacheStats::default(),
         }
     }
-    
-    fn len(&self) -> usize{
+
+    // 数量不设上限，由调用方按其他条件（比如总权重）淘汰
+    pub fn unbounded() -> Self{
+        let mut cache = Self::with_capacity(0);
+        cache.cap = usize::MAX;
+        cache
+    }
+
+    // 所有数据项默认存活 ttl
+    pub fn with_ttl(cap:usize, ttl:Duration) -> Self{
+        let mut cache = Self::with_capacity(cap);
+        cache.ttl = Some(ttl);
+        cache
+    }
+
+    pub fn set_ttl(&mut self, ttl:Option<Duration>){
+        self.ttl = ttl;
+    }
+
+    // 更换时钟，已有数据项的过期时刻按新时钟解释
+    pub fn set_clock(&mut self, clock:Arc<dyn Clock>){
+        self.clock = clock;
+    }
+
+    // 注册数据项离开缓存时的回调
+    pub fn set_eviction_listener<F>(&mut self, f:F)
+        where F: FnMut(K, V, RemovalCause) + Send + 'static {
+        self.listener = Some(Box::new(f));
+    }
+
+    fn notify(&mut self, key:K, val:V, cause:RemovalCause){
+        if let Some(f) = self.listener.as_mut() {
+            f(key, val, cause);
+        }
+    }
+
+    // 统计数据快照
+    pub fn stats(&self) -> CacheStats{
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self){
+        self.stats = CacheStats::default();
+    }
+
+    pub fn len(&self) -> usize{
         self.map.len()
     }
-    
-    fn is_empty(&self) -> bool{
+
+    pub fn is_empty(&self) -> bool{
         self.map.is_empty()
     }
-    
-    fn is_full(&self) -> bool{
+
+    pub fn is_full(&self) -> bool{
         self.map.len() >= self.cap
     }
+
+    pub fn cap(&self) -> usize{
+        self.cap
+    }
 }
 
 impl<K: Clone + Hash + Eq, V>LRU_Cache<K, V> {
-    fn insert(&mut self, key:K, val:V) -> Option<V>{
-        
+    // 插入，被替换的旧值以 Replaced 交给回调，没有回调时返回旧值
+    pub fn insert(&mut self, key:K, val:V) -> Option<V>{
+        let ttl = self.ttl;
+        self.insert_entry(key, val, ttl, self.replaced())
+    }
+
+    // 插入并单独指定存活时间
+    pub fn insert_with_ttl(&mut self, key:K, val:V, ttl:Duration) -> Option<V>{
+        self.insert_entry(key, val, Some(ttl), self.replaced())
+    }
+
+    // 插入并返回被替换的旧值，旧值不交给回调
+    pub fn insert_silent(&mut self, key:K, val:V) -> Option<V>{
+        let ttl = self.ttl;
+        self.insert_entry(key, val, ttl, None)
+    }
+
+    // 有回调时被替换的旧值交给回调
+    fn replaced(&self) -> Option<RemovalCause>{
+        self.listener.as_ref().map(|_| RemovalCause::Replaced)
+    }
+
+    // 插入，被替换的旧值交给回调而不是返回
+    pub fn put(&mut self, key:K, val:V){
+        let ttl = self.ttl;
+        self.insert_entry(key, val, ttl, Some(RemovalCause::Replaced));
+    }
+
+    // replaced 为 Some 时旧值交给回调，否则返回给调用者
+    fn insert_entry(&mut self, key:K, val:V, ttl:Option<Duration>,
+        replaced:Option<RemovalCause>) -> Option<V>{
+        let expires = ttl.map(|t| self.clock.now() + t);
+
         // 如果想要插入的数据已经在缓存中，则更新数据，移到链表头部，并将原始值返回；
-        if self.map.contains_key(&key){
-            self.access(&key);
-            let entry = &mut self.entries[self.head.unwrap()];
-            let old_val = entry.val.take();
-            entry.val = Some(val);
-            old_val
+        // 原来的值已过期时当作不存在
+        if let Some(&index) = self.map.get(&key){
+            self.access(index);
+            let expired = self.is_expired(index);
+            if expired {
+                self.stats.evictions += 1;
+                self.stats.inserts += 1;
+            } else {
+                self.stats.updates += 1;
+            }
+            let entry = &mut self.entries[index];
+            entry.expires = expires;
+            let old_val = entry.val.replace(val)?;
+            match (expired, replaced) {
+                (false, None) => return Some(old_val),
+                (false, Some(cause)) => self.notify(key, old_val, cause),
+                (true, _) => self.notify(key, old_val, RemovalCause::Expired),
+            }
+            None
         }else{  // 不存在键，插入
+            self.stats.inserts += 1;
+            if self.cap == 0 {
+                self.stats.evictions += 1;
+                self.notify(key, val, RemovalCause::Capacity);
+                return None;
+            }
             self.ensure_room();
-            
-            // 更新原始头指针
-            let new_index = self.entries.len();
-            self.head.map(|e|{
-                self.entries[e].prev = Some(new_index);
-            });
-            
-            // 新的节点
-            self.entries.push(Entry{
+
+            // 新的节点，有空位就复用空位
+            let entry = Entry{
                 key:key.clone(),
                 val:Some(val),
-                next:self.head,
+                next:None,
                 prev:None,
-            });
-            self.head = Some(new_index);
-            self.tail = self.tail.or(self.head);
+                expires:expires,
+            };
+            let new_index = match self.free.pop() {
+                Some(i) => {
+                    self.entries[i] = entry;
+                    i
+                },
+                None => {
+                    self.entries.push(entry);
+                    self.entries.len() - 1
+                },
+            };
+            self.push_front(new_index);
             self.map.insert(key, new_index);
             None
         }
     }
-    
+
     // 确保缓存容量足够，缓存满了就移除末尾的元素
     fn ensure_room(&mut self){
-        if self.is_full(){
-            self.remove_tail();
+        while self.is_full(){
+            self.evict_tail();
         }
     }
-    
-    fn remove_tail(&mut self){
-        if let Some(index) = self.tail{
-            self.remove_from_list(index);
-            if let key = self.entries[index].key.clone(){
-                self.map.remove(&key);
-            }
-            
-            if self.tail.is_none(){
-                self.head = None;
-            }
+
+    // 因容量不足淘汰尾部数据项
+    fn evict_tail(&mut self){
+        if let Some(index) = self.tail {
+            self.discard(index, RemovalCause::Capacity);
+        }
+    }
+
+    // 删除数据项并把值交给回调，过期的数据项总是以 Expired 通知
+    fn discard(&mut self, index:usize, cause:RemovalCause){
+        let cause = if self.is_expired(index) { RemovalCause::Expired } else { cause };
+        if cause == RemovalCause::Capacity || cause == RemovalCause::Expired {
+            self.stats.evictions += 1;
+        }
+        let key = self.entries[index].key.clone();
+        if let Some(val) = self.remove_index(index) {
+            self.notify(key, val, cause);
         }
     }
-    
+
+    fn remove_tail(&mut self) -> Option<(K, V)>{
+        let index = self.tail?;
+        let key = self.entries[index].key.clone();
+        self.remove_index(index).map(|v| (key, v))
+    }
+
+    // 将数据项从链表中摘下，并修正头尾指针
     fn remove_from_list(&mut self, index:usize){
         let (prev, next) = {
             let entry = self.entries.get_mut(index).unwrap();
             (entry.prev.take(), entry.next.take())
         };
-        
-        match(prev,next) {
-            // 数据项在缓存的中间
-            (Some(j),Some(k)) =>{
-                let head = &mut self.entries[j];
-                head.next = next;
-                let next_entry = &mut self.entries[k];
-                next_entry.prev = prev;
+
+        match prev {
+            Some(j) => self.entries[j].next = next,
+            None => self.head = next,       // 数据项在缓存的头部
+        }
+        match next {
+            Some(k) => self.entries[k].prev = prev,
+            None => self.tail = prev,       // 数据项在缓存的尾部
+        }
+    }
+
+    // 将数据项放到链表头部
+    fn push_front(&mut self, index:usize){
+        self.entries[index].prev = None;
+        self.entries[index].next = self.head;
+        if let Some(h) = self.head {
+            self.entries[h].prev = Some(index);
+        }
+        self.head = Some(index);
+        self.tail = self.tail.or(self.head);
+    }
+
+    // 访问某个数据项，移除原来位置的值并在头部加入
+    fn access(&mut self, index:usize){
+        if self.head != Some(index) {
+            self.remove_from_list(index);
+            self.push_front(index);
+        }
+    }
+
+    fn is_expired(&self, index:usize) -> bool{
+        match self.entries[index].expires {
+            Some(t) => self.clock.now() >= t,
+            None => false,
+        }
+    }
+
+    // 查找未过期的数据项，过期的直接删除
+    // 查找时记录命中和未命中
+    fn lookup(&mut self, key:&K) -> Option<usize>{
+        let index = match self.map.get(key) {
+            Some(&index) => index,
+            None => {
+                self.stats.misses += 1;
+                return None;
+            },
+        };
+        if self.is_expired(index) {
+            self.discard(index, RemovalCause::Expired);
+            self.stats.misses += 1;
+            return None;
+        }
+        self.stats.hits += 1;
+        Some(index)
+    }
+
+    pub fn get(&mut self, key:&K) -> Option<&V>{
+        let index = self.lookup(key)?;
+        self.access(index);
+        self.entries[index].val.as_ref()
+    }
+
+    pub fn get_mut(&mut self, key:&K) -> Option<&mut V>{
+        let index = self.lookup(key)?;
+        self.access(index);
+        self.entries[index].val.as_mut()
+    }
+
+    // 键不存在时用 f 计算值并插入，返回缓存中的值；容量为 0 时不缓存，返回计算结果
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key:K, f:F) -> Fetched<'_, V>{
+        match self.try_get_or_insert_with(key, || Ok::<V, ()>(f())) {
+            Ok(v) => v,
+            Err(()) => unreachable!(),
+        }
+    }
+
+    // 同 get_or_insert_with，计算失败时不插入并返回错误
+    pub fn try_get_or_insert_with<E, F>(&mut self, key:K, f:F) -> Result<Fetched<'_, V>, E>
+        where F: FnOnce() -> Result<V, E> {
+        let index = match self.lookup(&key) {
+            Some(index) => {
+                self.access(index);
+                index
             },
-            
-            // 数据项在缓存的尾部
-            (Some(j),None) =>{
-                let head = &mut self.entries[j];
-                head.next = None;
-                self.tail = prev;
+            None if self.cap == 0 => {
+                return Ok(Fetched::Computed(f()?));
             },
+            None => {
+                self.insert(key, f()?);
+                self.head.unwrap()
+            },
+        };
+        Ok(Fetched::Cached(self.entries[index].val.as_ref().unwrap()))
+    }
 
-            // 数据项在缓存的头部
-            _ =>{
-                if self.len() > 1{
-                    let head = &mut self.entries[0];
-                    head.next = None;
-                    let next = &mut self.entries[1];
-                    next.prev = None;
-                }
+    // 查看但不改变使用顺序
+    pub fn peek(&self, key:&K) -> Option<&V>{
+        let index = *self.map.get(key)?;
+        if self.is_expired(index) {
+            return None;
+        }
+        self.entries[index].val.as_ref()
+    }
+
+    // 查看最久未使用且未过期的数据项
+    pub fn peek_lru(&self) -> Option<(&K, &V)>{
+        let mut cur = self.tail;
+        while let Some(index) = cur {
+            let entry = &self.entries[index];
+            if !self.is_expired(index) {
+                return entry.val.as_ref().map(|v| (&entry.key, v));
+            }
+            cur = entry.prev;
+        }
+        None
+    }
+
+    // 移除并返回最久未使用且未过期的数据项，途经的过期项一并删除
+    pub fn pop_lru(&mut self) -> Option<(K, V)>{
+        while let Some(index) = self.tail {
+            if !self.is_expired(index) {
+                return self.remove_tail();
+            }
+            self.discard(index, RemovalCause::Expired);
+        }
+        None
+    }
+
+    // 删除，值以 Explicit 交给回调，没有回调时返回值
+    pub fn remove(&mut self, key:&K) -> Option<V>{
+        if self.listener.is_some() {
+            self.invalidate(key);
+            return None;
+        }
+        self.remove_silent(key)
+    }
+
+    // 删除并返回值，值不交给回调
+    pub fn remove_silent(&mut self, key:&K) -> Option<V>{
+        let index = *self.map.get(key)?;
+        if self.is_expired(index) {
+            self.discard(index, RemovalCause::Expired);
+            return None;
+        }
+        self.remove_index(index)
+    }
+
+    // 主动删除，值交给回调，返回键是否存在
+    pub fn invalidate(&mut self, key:&K) -> bool{
+        match self.map.get(key) {
+            Some(&index) => {
+                let live = !self.is_expired(index);
+                self.discard(index, RemovalCause::Explicit);
+                live
             },
+            None => false,
         }
     }
-    
-    // 获取某个键的值，移除原来位置的值并在头部加入
-    fn access(&mut self, key:&K){
-        let index = *self.map.get(key).unwrap();
+
+    fn remove_index(&mut self, index:usize) -> Option<V>{
         self.remove_from_list(index);
-        self.head = Some(index);
+        self.map.remove(&self.entries[index].key);
+        self.free.push(index);
+        self.entries[index].val.take()
+    }
+
+    pub fn contains(&self, key:&K)->bool{
+        match self.map.get(key) {
+            Some(&index) => !self.is_expired(index),
+            None => false,
+        }
+    }
+
+    // 清理所有过期的数据项，返回清理的数量
+    pub fn purge_expired(&mut self) -> usize{
+        let mut count = 0;
+        let mut cur = self.tail;
+        while let Some(index) = cur {
+            cur = self.entries[index].prev;
+            if self.is_expired(index) {
+                self.discard(index, RemovalCause::Expired);
+                count += 1;
+            }
+        }
+        count
     }
-    
-    fn get(&mut self, key:&K) -> Option<&V>{
-        if self.map.contains_key(key){
-            self.access(key);
+
+    // 调整容量，缩小时从尾部淘汰
+    pub fn resize(&mut self, cap:usize){
+        self.cap = cap;
+        while self.len() > cap {
+            self.evict_tail();
         }
-        
-        let entries = &self.entries;
-        self.map.get(key).and_then(move |&i| {
-            entries[i].val.as_ref()})
     }
-    
-    fn remove(&mut self, key:&K) -> Option<V>{
-        self.map.remove(key).and_then(|i|{
-            self.remove_from_list(i);
-            self.entries[i].val.take()
-        })
+
+    // 清空缓存，有回调时所有值都交给回调
+    pub fn clear(&mut self){
+        if self.listener.is_some() {
+            while let Some(index) = self.tail {
+                self.discard(index, RemovalCause::Explicit);
+            }
+        }
+        self.reset();
     }
 
-    fn contains(&mut self, key:&K)->bool{
-        self.map.contains_key(key)
+    fn reset(&mut self){
+        self.head = None;
+        self.tail = None;
+        self.map.clear();
+        self.entries.clear();
+        self.free.clear();
+    }
+
+    // 按从最近使用到最久未使用的顺序取出全部未过期的数据项，缓存被清空
+    pub fn drain(&mut self) -> std::vec::IntoIter<(K, V)>{
+        let mut items = Vec::with_capacity(self.len());
+        while let Some(index) = self.head {
+            self.remove_from_list(index);
+            let expired = self.is_expired(index);
+            let key = self.entries[index].key.clone();
+            match self.entries[index].val.take() {
+                Some(v) if expired => self.notify(key, v, RemovalCause::Expired),
+                Some(v) => items.push((key, v)),
+                None => {},
+            }
+        }
+        self.reset();
+        items.into_iter()
+    }
+
+    // 按从最近使用到最久未使用的顺序遍历，跳过过期的数据项
+    pub fn iter(&self) -> Iter<'_, K, V>{
+        Iter{ entries:&self.entries, next:self.head, len:self.len(), now:self.clock.now() }
+    }
+}
+
+// 快照：按从最近使用到最久未使用的顺序保存 (键, 值, 剩余存活时间)，
+// 时钟的起点在重启后会变化，所以保存的是剩余时间而不是过期时刻
+impl<K: Clone + Hash + Eq, V> LRU_Cache<K, V> {
+    pub fn to_snapshot(&self) -> Vec<u8>
+        where K: Serialize, V: Serialize {
+        let now = self.clock.now();
+        let mut items = Vec::with_capacity(self.len());
+        let mut next = self.head;
+        while let Some(index) = next {
+            let entry = &self.entries[index];
+            next = entry.next;
+            if let Some(val) = entry.val.as_ref() {
+                match entry.expires {
+                    Some(t) if now >= t => {},
+                    Some(t) => items.push((&entry.key, val, Some(t - now))),
+                    None => items.push((&entry.key, val, None)),
+                }
+            }
+        }
+        bincode::serialize(&items).unwrap()
+    }
+
+    // 用快照替换缓存的内容，恢复原来的 LRU 顺序，返回恢复的数据项数。
+    // 快照比容量大时只保留最近使用的 cap 项
+    pub fn restore_snapshot(&mut self, bytes:&[u8]) -> Result<usize, SnapshotError>
+        where K: DeserializeOwned, V: DeserializeOwned {
+        let mut items: Vec<(K, V, Option<Duration>)> = bincode::deserialize(bytes)
+            .map_err(|_| SnapshotError::Decode)?;
+        self.clear();
+        items.truncate(self.cap);
+
+        // 从最久未使用的开始插入，最后插入的在链表头部
+        let count = items.len();
+        for (key, val, ttl) in items.into_iter().rev() {
+            self.insert_entry(key, val, ttl, None);
+        }
+        Ok(count)
+    }
+
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path:P) -> Result<(), SnapshotError>
+        where K: Serialize, V: Serialize {
+        fs::write(path, self.to_snapshot())?;
+        Ok(())
+    }
+
+    pub fn load_snapshot<P: AsRef<Path>>(&mut self, path:P) -> Result<usize, SnapshotError>
+        where K: DeserializeOwned, V: DeserializeOwned {
+        let bytes = fs::read(path)?;
+        self.restore_snapshot(&bytes)
+    }
+}
+
+impl<K: Clone + Hash + Eq, V> Default for LRU_Cache<K, V> {
+    fn default() -> Self{
+        Self::new()
+    }
+}
+
+// 遍历器，沿 next 指针从头部走到尾部
+pub struct Iter<'a, K, V> {
+    entries:&'a Vec<Entry<K, V>>,
+    next:Option<usize>,
+    len:usize,
+    now:Duration,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item>{
+        loop {
+            let entry = &self.entries[self.next?];
+            self.next = entry.next;
+            self.len -= 1;
+            if entry.expires.map_or(false, |t| self.now >= t) {
+                continue;
+            }
+            return entry.val.as_ref().map(|v| (&entry.key, v));
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>){
+        (0, Some(self.len))
+    }
+}
+
+impl<'a, K: Clone + Hash + Eq, V> IntoIterator for &'a LRU_Cache<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter{
+        self.iter()
     }
 }
 
@@ -185,4 +762,257 @@ mod tests {
         cache.insert("hhh", 6);
         assert!(cache.contains(&"hhh"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_order() {
+        let mut cache = LRU_Cache::with_capacity(3);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.insert("c", 3);
+
+        // get 会提升，peek 不会
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.peek(&"b"), Some(&2));
+        assert_eq!(cache.peek_lru(), Some((&"b", &2)));
+        *cache.get_mut(&"b").unwrap() += 10;
+        let order: Vec<_> = cache.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(order, vec![("b", 12), ("a", 1), ("c", 3)]);
+
+        assert_eq!(cache.insert("a", 0), Some(1));
+        assert_eq!(cache.pop_lru(), Some(("c", 3)));
+        assert_eq!(cache.remove(&"b"), Some(12));
+        assert_eq!(cache.iter().count(), 1);
+        assert_eq!(cache.peek_lru(), Some((&"a", &0)));
+    }
+
+    #[test]
+    fn test_resize_drain() {
+        let mut cache = LRU_Cache::with_capacity(4);
+        for i in 0..4 {
+            cache.insert(i, i * 10);
+        }
+        cache.resize(2);
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(&0) && !cache.contains(&1));
+
+        cache.resize(3);
+        cache.insert(4, 40);
+        assert_eq!(cache.drain().collect::<Vec<_>>(), vec![(4, 40), (3, 30), (2, 20)]);
+        assert!(cache.is_empty());
+        assert_eq!(cache.peek_lru(), None);
+
+        cache.insert(5, 50);
+        cache.clear();
+        assert!(cache.is_empty() && cache.iter().next().is_none());
+    }
+
+    #[test]
+    fn test_churn_bounded() {
+        let mut cache = LRU_Cache::with_capacity(16);
+        for i in 0..100_000 {
+            cache.insert(i, i);
+            if i % 3 == 0 {
+                cache.remove(&(i - 1));
+            }
+            if i % 7 == 0 {
+                cache.pop_lru();
+            }
+            assert!(cache.entries.len() <= 16);
+        }
+        assert_eq!(cache.len(), cache.iter().count());
+        assert!(cache.len() <= 16);
+        assert!(cache.entries.len() + cache.free.len() <= 32);
+    }
+
+    #[test]
+    fn test_ttl() {
+        let clock = Arc::new(ManualClock::new());
+        let mut cache = LRU_Cache::with_ttl(4, Duration::from_secs(10));
+        cache.set_clock(clock.clone());
+
+        cache.insert("a", 1);
+        cache.insert_with_ttl("b", 2, Duration::from_secs(30));
+        clock.advance(Duration::from_secs(5));
+        cache.insert("c", 3);
+        assert_eq!(cache.get(&"a"), Some(&1));
+
+        // a 过期，b 和 c 还在
+        clock.advance(Duration::from_secs(6));
+        assert_eq!(cache.get(&"a"), None);
+        assert!(!cache.contains(&"a"));
+        assert_eq!(cache.peek(&"c"), Some(&3));
+        assert_eq!(cache.len(), 2);
+
+        // c 过期后只剩 b
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(cache.peek(&"c"), None);
+        assert_eq!(cache.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec!["b"]);
+        assert_eq!(cache.peek_lru(), Some((&"b", &2)));
+        assert_eq!(cache.insert("c", 4), None);
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(cache.purge_expired(), 2);
+        assert!(cache.is_empty());
+        assert_eq!(cache.free.len(), cache.entries.len());
+    }
+
+    #[test]
+    fn test_eviction_listener() {
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let clock = Arc::new(ManualClock::new());
+        let mut cache = LRU_Cache::with_capacity(2);
+        cache.set_clock(clock.clone());
+        let log = removed.clone();
+        cache.set_eviction_listener(move |k, v, cause| log.lock().unwrap().push((k, v, cause)));
+
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.insert("c", 3);
+        cache.put("b", 20);
+        // 有回调时 insert 和 remove 的值交给回调，不再返回
+        assert_eq!(cache.insert("b", 200), None);
+        assert!(cache.invalidate(&"c"));
+        cache.insert_with_ttl("d", 4, Duration::from_secs(1));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(cache.get(&"d"), None);
+        assert_eq!(cache.remove(&"b"), None);
+        assert_eq!(cache.remove(&"b"), None);
+        cache.insert("e", 5);
+        cache.clear();
+
+        assert_eq!(*removed.lock().unwrap(), vec![
+            ("a", 1, RemovalCause::Capacity),
+            ("b", 2, RemovalCause::Replaced),
+            ("b", 20, RemovalCause::Replaced),
+            ("c", 3, RemovalCause::Explicit),
+            ("d", 4, RemovalCause::Expired),
+            ("b", 200, RemovalCause::Explicit),
+            ("e", 5, RemovalCause::Explicit),
+        ]);
+    }
+
+    #[test]
+    fn test_silent() {
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let clock = Arc::new(ManualClock::new());
+        let mut cache = LRU_Cache::with_capacity(2);
+        cache.set_clock(clock.clone());
+
+        // 没有回调时 insert 和 remove 返回值
+        cache.insert("a", 1);
+        assert_eq!(cache.insert("a", 2), Some(1));
+        assert_eq!(cache.remove(&"a"), Some(2));
+
+        // silent 版本把值交还给调用者，不通知
+        let log = removed.clone();
+        cache.set_eviction_listener(move |k, v, cause| log.lock().unwrap().push((k, v, cause)));
+        assert_eq!(cache.insert_silent("a", 1), None);
+        assert_eq!(cache.insert_silent("a", 10), Some(1));
+        assert_eq!(cache.remove_silent(&"a"), Some(10));
+        cache.insert("b", 2);
+        cache.insert("c", 3);
+        assert_eq!(cache.pop_lru(), Some(("b", 2)));
+        assert_eq!(cache.drain().collect::<Vec<_>>(), vec![("c", 3)]);
+        assert!(removed.lock().unwrap().is_empty());
+
+        // 已过期的旧值没有交还给调用者，仍然以 Expired 通知
+        cache.insert_with_ttl("d", 4, Duration::from_secs(1));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(cache.insert_silent("d", 5), None);
+        assert_eq!(cache.remove_silent(&"d"), Some(5));
+        assert_eq!(*removed.lock().unwrap(), vec![("d", 4, RemovalCause::Expired)]);
+    }
+
+    #[test]
+    fn test_stats() {
+        let clock = Arc::new(ManualClock::new());
+        let mut cache = LRU_Cache::with_capacity(2);
+        cache.set_clock(clock.clone());
+        cache.insert(1, 1);
+        cache.insert(2, 2);
+        cache.insert(2, 20);
+        cache.insert(3, 3);
+        cache.get(&3);
+        cache.get(&1);
+        cache.peek(&2);
+        cache.insert_with_ttl(4, 4, Duration::from_secs(1));
+        clock.advance(Duration::from_secs(1));
+        cache.get(&4);
+
+        assert_eq!(cache.stats(), CacheStats{
+            hits:1, misses:2, inserts:4, updates:1, evictions:3,
+        });
+        assert_eq!(cache.stats().hit_ratio(), 1.0 / 3.0);
+        cache.reset_stats();
+        assert_eq!(cache.stats().hit_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_unbounded() {
+        let mut cache = LRU_Cache::unbounded();
+        for i in 0..10000 {
+            cache.insert(i, i);
+        }
+        assert_eq!(cache.len(), 10000);
+        assert_eq!(cache.cap(), usize::MAX);
+        assert!(!cache.is_full());
+        assert_eq!(cache.pop_lru(), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        let mut cache = LRU_Cache::with_capacity(2);
+        assert_eq!(*cache.get_or_insert_with("a", || 1), 1);
+        assert_eq!(*cache.get_or_insert_with("a", || 2), 1);
+        assert_eq!(cache.try_get_or_insert_with("b", || Err::<i32, &str>("fail")), Err("fail"));
+        assert!(!cache.contains(&"b"));
+        assert_eq!(cache.try_get_or_insert_with("b", || Ok::<i32, &str>(3)), Ok(Fetched::Cached(&3)));
+
+        // 容量为 0 时照常返回计算结果，但不缓存
+        let mut empty = LRU_Cache::with_capacity(0);
+        assert_eq!(empty.get_or_insert_with("a", || 1), Fetched::Computed(1));
+        assert_eq!(empty.get_or_insert_with("a", || 2).into_owned(), 2);
+        assert_eq!(empty.try_get_or_insert_with("a", || Err::<i32, &str>("fail")), Err("fail"));
+        assert!(empty.is_empty());
+        assert_eq!(empty.stats().misses, 3);
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().misses, 3);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let clock = Arc::new(ManualClock::new());
+        let mut cache = LRU_Cache::with_capacity(4);
+        cache.set_clock(clock.clone());
+        cache.insert("a".to_string(), 1);
+        cache.insert_with_ttl("b".to_string(), 2, Duration::from_secs(10));
+        cache.insert_with_ttl("c".to_string(), 3, Duration::from_secs(1));
+        cache.insert("d".to_string(), 4);
+        cache.get(&"a".to_string());
+        clock.advance(Duration::from_secs(4));
+
+        let path = std::env::temp_dir().join("rust_studying_test_lru.snapshot");
+        cache.save_snapshot(&path).unwrap();
+
+        // 新进程中的缓存：时钟从头开始，过期的 c 不会恢复，b 还剩 6 秒
+        let clock = Arc::new(ManualClock::new());
+        let mut restored = LRU_Cache::with_capacity(4);
+        restored.set_clock(clock.clone());
+        restored.insert("x".to_string(), 0);
+        assert_eq!(restored.load_snapshot(&path), Ok(3));
+        fs::remove_file(&path).unwrap();
+
+        let order: Vec<_> = restored.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        assert_eq!(order, vec![("a", 1), ("d", 4), ("b", 2)]);
+        clock.advance(Duration::from_secs(6));
+        assert!(!restored.contains(&"b".to_string()));
+
+        // 容量变小时只保留最近使用的
+        let mut small: LRU_Cache<String, i32> = LRU_Cache::with_capacity(2);
+        assert_eq!(small.restore_snapshot(&cache.to_snapshot()), Ok(2));
+        assert_eq!(small.peek_lru(), Some((&"d".to_string(), &4)));
+
+        assert_eq!(small.restore_snapshot(&[1, 2, 3]), Err(SnapshotError::Decode));
+        assert_eq!(small.load_snapshot("/nonexistent/lru.snapshot"),
+            Err(SnapshotError::Io(io::ErrorKind::NotFound)));
+    }
+}
@@ -0,0 +1,161 @@
+// 用 LRU 缓存做记忆化
+// Memo 把纯函数 Fn(K) -> V 包装成带缓存的函数，缓存容量有上限。
+// 递归的函数（比如动态规划）通过参数拿到自身的记忆化版本，
+// 计算期间不持有缓存的借用，所以递归调用不会出现重复借用。
+// memoize! 宏直接定义一个记忆化的函数，每个线程一份缓存。
+// 多线程共享时使用 ShardedCache::get_or_insert_with
+
+use std::hash::Hash;
+use std::cell::RefCell;
+use crate::LRU::lru::{LRU_Cache, CacheStats};
+
+// 递归函数的形式：第一个参数是记忆化后的自身
+type RecFn<K, V> = Box<dyn Fn(&dyn Fn(K) -> V, K) -> V>;
+
+pub struct Memo<K, V> {
+    cache:RefCell<LRU_Cache<K, V>>,
+    f:RecFn<K, V>,
+}
+
+impl<K: Clone + Hash + Eq + 'static, V: Clone + 'static> Memo<K, V> {
+    pub fn new<F: Fn(K) -> V + 'static>(cap:usize, f:F) -> Self{
+        Self::recursive(cap, move |_, k| f(k))
+    }
+
+    // 递归函数通过第一个参数调用自身，例如
+    // Memo::recursive(100, |fib, n| if n < 2 { n } else { fib(n - 1) + fib(n - 2) })
+    pub fn recursive<F>(cap:usize, f:F) -> Self
+        where F: Fn(&dyn Fn(K) -> V, K) -> V + 'static {
+        Memo{ cache:RefCell::new(LRU_Cache::with_capacity(cap)), f:Box::new(f) }
+    }
+
+    pub fn call(&self, key:K) -> V{
+        if let Some(v) = self.cache.borrow_mut().get(&key) {
+            return v.clone();
+        }
+        let val = (self.f)(&|k| self.call(k), key.clone());
+        self.cache.borrow_mut().insert(key, val.clone());
+        val
+    }
+
+    pub fn stats(&self) -> CacheStats{
+        self.cache.borrow().stats()
+    }
+
+    pub fn len(&self) -> usize{
+        self.cache.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.cache.borrow().is_empty()
+    }
+
+    pub fn clear(&self){
+        self.cache.borrow_mut().clear();
+    }
+}
+
+// 定义记忆化的函数，函数体中可以递归调用自身：
+// memoize! {
+//     100;
+//     fn fib(n:u64) -> u64 {
+//         if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+//     }
+// }
+// 参数组成的元组作为缓存的键，参数和返回值都需要 Clone
+#[macro_export]
+macro_rules! memoize {
+    ($cap:expr; $vis:vis fn $name:ident($($arg:ident : $ty:ty),+ $(,)?) -> $ret:ty $body:block) => {
+        $vis fn $name($($arg: $ty),+) -> $ret {
+            ::std::thread_local! {
+                static CACHE: ::std::cell::RefCell<$crate::LRU::lru::LRU_Cache<($($ty,)+), $ret>> =
+                    ::std::cell::RefCell::new($crate::LRU::lru::LRU_Cache::with_capacity($cap));
+            }
+            let key = ($($arg.clone(),)+);
+            if let Some(v) = CACHE.with(|c| c.borrow_mut().get(&key).cloned()) {
+                return v;
+            }
+            #[allow(clippy::redundant_closure_call)]
+            let val: $ret = (move || $body)();
+            CACHE.with(|c| c.borrow_mut().insert(key, val.clone()));
+            val
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::Cell;
+
+    #[test]
+    fn it_works() {
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        let square = Memo::new(2, move |x: u64| {
+            counter.set(counter.get() + 1);
+            x * x
+        });
+        assert_eq!(square.call(3), 9);
+        assert_eq!(square.call(3), 9);
+        assert_eq!(calls.get(), 1);
+
+        // 容量有限，最久未使用的会被淘汰
+        square.call(4);
+        square.call(5);
+        assert_eq!(square.len(), 2);
+        assert_eq!(square.call(3), 9);
+        assert_eq!(calls.get(), 4);
+        square.clear();
+        assert!(square.is_empty());
+    }
+
+    #[test]
+    fn test_recursive() {
+        let fib = Memo::recursive(100, |fib, n: u64| if n < 2 { n } else { fib(n - 1) + fib(n - 2) });
+        assert_eq!(fib.call(90), 2880067194370816120);
+        assert_eq!(fib.stats().misses, 91);
+
+        // 容量很小时结果仍然正确
+        let fib = Memo::recursive(3, |fib, n: u64| if n < 2 { n } else { fib(n - 1) + fib(n - 2) });
+        assert_eq!(fib.call(30), 832040);
+
+        // 二维动态规划：网格中从左上角到 (r, c) 的路径数
+        let paths = Memo::recursive(1000, |paths, (r, c): (u32, u32)| {
+            if r == 0 || c == 0 { 1u64 } else { paths((r - 1, c)) + paths((r, c - 1)) }
+        });
+        assert_eq!(paths.call((16, 16)), 601080390);
+    }
+
+    memoize! {
+        200;
+        fn fib(n:u64) -> u64 {
+            if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+        }
+    }
+
+    memoize! {
+        1000;
+        fn edit_distance(a:Rc<Vec<u8>>, b:Rc<Vec<u8>>, i:usize, j:usize) -> usize {
+            if i == 0 {
+                return j;
+            }
+            if j == 0 {
+                return i;
+            }
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            (edit_distance(a.clone(), b.clone(), i - 1, j) + 1)
+                .min(edit_distance(a.clone(), b.clone(), i, j - 1) + 1)
+                .min(edit_distance(a, b, i - 1, j - 1) + cost)
+        }
+    }
+
+    #[test]
+    fn test_macro() {
+        assert_eq!(fib(90), 2880067194370816120);
+        let a = Rc::new(b"kitten".to_vec());
+        let b = Rc::new(b"sitting".to_vec());
+        assert_eq!(edit_distance(a.clone(), b.clone(), a.len(), b.len()), 3);
+    }
+}
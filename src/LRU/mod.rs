@@ -1,2 +1,13 @@
-mod lru;
-mod base58;
\ No newline at end of file
+pub mod lru;
+pub mod weighted;
+pub mod sharded;
+pub mod policy;
+pub mod lfu;
+pub mod arc;
+pub mod tiny_lfu;
+pub mod loading;
+pub mod clock;
+pub mod clock_pro;
+pub mod tiered;
+pub mod memoize;
+pub mod base58;
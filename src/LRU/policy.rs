@@ -0,0 +1,154 @@
+// 缓存淘汰策略的统一接口
+// LRU、LFU、ARC 等缓存都实现 CachePolicy，调用方可以用 Box<dyn CachePolicy<K, V>>
+// 在不同策略之间切换，并用同一份访问记录比较命中率
+
+use std::hash::Hash;
+use crate::LRU::lru::{LRU_Cache, CacheStats};
+
+pub trait CachePolicy<K, V> {
+    // 插入数据项，返回被替换的旧值
+    fn insert(&mut self, key:K, val:V) -> Option<V>;
+
+    // 读取数据项，并按策略记录这次访问
+    fn get(&mut self, key:&K) -> Option<&V>;
+
+    // 读取数据项，不影响淘汰顺序
+    fn peek(&self, key:&K) -> Option<&V>;
+
+    fn remove(&mut self, key:&K) -> Option<V>;
+
+    fn contains(&self, key:&K) -> bool;
+
+    fn len(&self) -> usize;
+
+    fn cap(&self) -> usize;
+
+    fn clear(&mut self);
+
+    fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+}
+
+impl<K: Clone + Hash + Eq, V> CachePolicy<K, V> for LRU_Cache<K, V> {
+    fn insert(&mut self, key:K, val:V) -> Option<V>{
+        LRU_Cache::insert(self, key, val)
+    }
+
+    fn get(&mut self, key:&K) -> Option<&V>{
+        LRU_Cache::get(self, key)
+    }
+
+    fn peek(&self, key:&K) -> Option<&V>{
+        LRU_Cache::peek(self, key)
+    }
+
+    fn remove(&mut self, key:&K) -> Option<V>{
+        LRU_Cache::remove(self, key)
+    }
+
+    fn contains(&self, key:&K) -> bool{
+        LRU_Cache::contains(self, key)
+    }
+
+    fn len(&self) -> usize{
+        LRU_Cache::len(self)
+    }
+
+    fn cap(&self) -> usize{
+        LRU_Cache::cap(self)
+    }
+
+    fn clear(&mut self){
+        LRU_Cache::clear(self)
+    }
+}
+
+// 在缓存上重放访问记录：命中则读取，未命中则插入
+// 重放过程中没有删除，所以淘汰数等于插入数减去最终的数据量
+pub fn replay<K, I>(cache:&mut dyn CachePolicy<K, ()>, trace:I) -> CacheStats
+    where K: Clone, I: IntoIterator<Item = K> {
+    let start = cache.len() as u64;
+    let mut stats = CacheStats::default();
+    for key in trace {
+        if cache.get(&key).is_some() {
+            stats.hits += 1;
+        } else {
+            stats.misses += 1;
+            stats.inserts += 1;
+            cache.insert(key, ());
+        }
+    }
+    stats.evictions = (start + stats.inserts).saturating_sub(cache.len() as u64);
+    stats
+}
+
+// 重放访问记录，返回命中率
+pub fn hit_ratio<K: Clone>(cache:&mut dyn CachePolicy<K, ()>, trace:&[K]) -> f64{
+    replay(cache, trace.iter().cloned()).hit_ratio()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LRU::lfu::LFU_Cache;
+    use crate::LRU::arc::ARC_Cache;
+    use crate::LRU::clock::CLOCK_Cache;
+    use crate::LRU::clock_pro::CLOCKPro_Cache;
+
+    #[test]
+    fn it_works() {
+        let policies: Vec<Box<dyn CachePolicy<u32, u32>>> = vec![
+            Box::new(LRU_Cache::with_capacity(2)),
+            Box::new(LFU_Cache::with_capacity(2)),
+            Box::new(ARC_Cache::with_capacity(2)),
+            Box::new(CLOCK_Cache::with_capacity(2)),
+            Box::new(CLOCKPro_Cache::with_capacity(2)),
+        ];
+        for mut cache in policies {
+            assert!(cache.is_empty());
+            cache.insert(1, 10);
+            cache.insert(2, 20);
+            assert_eq!(cache.get(&1), Some(&10));
+            cache.insert(3, 30);
+            assert_eq!(cache.len(), 2);
+            assert_eq!(cache.cap(), 2);
+            assert!(cache.contains(&1));
+            assert!(!cache.contains(&2));
+            assert_eq!(cache.remove(&1), Some(10));
+            cache.clear();
+            assert!(cache.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_scan_resistance() {
+        // 热点数据被一次性的顺序扫描打断，LFU 和 ARC 能保住热点，LRU 不能
+        let mut trace = Vec::new();
+        for round in 0..20 {
+            for _ in 0..5 {
+                trace.extend(0..8);
+            }
+            trace.extend((0..30).map(|i| 1000 + round * 30 + i));
+        }
+
+        let lru = hit_ratio(&mut LRU_Cache::with_capacity(10), &trace);
+        let lfu = hit_ratio(&mut LFU_Cache::with_capacity(10), &trace);
+        let arc = hit_ratio(&mut ARC_Cache::with_capacity(10), &trace);
+        assert!(lfu > lru, "lfu {} lru {}", lfu, lru);
+        assert!(arc > lru, "arc {} lru {}", arc, lru);
+    }
+
+    #[test]
+    fn test_replay() {
+        let trace = vec![1, 2, 1, 3, 1, 2];
+        let mut cache = LRU_Cache::with_capacity(2);
+        let stats = replay(&mut cache, trace);
+        assert_eq!(stats, CacheStats{ hits:2, misses:4, inserts:4, updates:0, evictions:2 });
+
+        // 与 LRU_Cache 自己的统计一致
+        assert_eq!(cache.stats().hits, stats.hits);
+        assert_eq!(cache.stats().misses, stats.misses);
+        assert_eq!(cache.stats().evictions, stats.evictions);
+    }
+}
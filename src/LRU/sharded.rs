@@ -0,0 +1,299 @@
+// 分片的并发 LRU 缓存
+// 键按哈希值分配到 N 个分片，每个分片是一把锁保护的 LRU_Cache，
+// 不同分片上的读写互不阻塞，所有方法只需 &self，可以在线程间共享。
+// get_or_insert_with 会合并同一个键上并发的未命中：只有一个线程执行加载，其余线程等待它的结果
+
+use std::hash::{BuildHasher, Hash};
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use crate::LRU::lru::LRU_Cache;
+
+// 默认分片数
+const SHARDS:usize = 16;
+
+// 每个分片至少的容量，容量太小时减少分片数
+const MIN_SHARD_CAP:usize = 16;
+
+// 正在加载的键，等待的线程在 cond 上等待 (是否完成, 加载结果)
+struct Pending<V> {
+    state:Mutex<(bool, Option<V>)>,
+    cond:Condvar,
+}
+
+pub struct ShardedCache<K, V> {
+    shards:Vec<Mutex<LRU_Cache<K, V>>>,
+    hasher:RandomState,
+    cap:usize,
+    loading:Mutex<HashMap<K, Arc<Pending<V>>>>,
+}
+
+// 加载结束（包括失败和 panic）时移除正在加载的记录并唤醒等待的线程
+struct LoadGuard<'a, K: Hash + Eq, V> {
+    loading:&'a Mutex<HashMap<K, Arc<Pending<V>>>>,
+    key:&'a K,
+    pending:Arc<Pending<V>>,
+}
+
+impl<K: Hash + Eq, V> Drop for LoadGuard<'_, K, V> {
+    fn drop(&mut self){
+        if let Ok(mut loading) = self.loading.lock() {
+            loading.remove(self.key);
+        }
+        let mut state = match self.pending.state.lock() {
+            Ok(state) => state,
+            Err(e) => e.into_inner(),
+        };
+        state.0 = true;
+        self.pending.cond.notify_all();
+    }
+}
+
+impl<K: Clone + Hash + Eq, V> ShardedCache<K, V> {
+    pub fn new(cap:usize) -> Self{
+        Self::with_shards(cap, SHARDS)
+    }
+
+    // 总容量 cap 平均分给各个分片，总数不会超过 cap。
+    // 分片太小时键分布不均会提前淘汰，所以每个分片至少 MIN_SHARD_CAP 项，
+    // 实际分片数可能少于 shards，容量很小时只有一个分片
+    pub fn with_shards(cap:usize, shards:usize) -> Self{
+        assert!(shards > 0, "shard count must > 0");
+        let shards = shards.min(cap / MIN_SHARD_CAP).max(1);
+        let shards = (0..shards)
+            .map(|i| {
+                let shard_cap = cap / shards + if i < cap % shards { 1 } else { 0 };
+                Mutex::new(LRU_Cache::with_capacity(shard_cap))
+            })
+            .collect();
+        ShardedCache{ shards, hasher:RandomState::new(), cap, loading:Mutex::new(HashMap::new()) }
+    }
+
+    // 键所在的分片
+    fn shard(&self, key:&K) -> MutexGuard<'_, LRU_Cache<K, V>>{
+        let index = self.hasher.hash_one(key) as usize % self.shards.len();
+        self.shards[index].lock().unwrap()
+    }
+
+    pub fn cap(&self) -> usize{
+        self.cap
+    }
+
+    pub fn shard_count(&self) -> usize{
+        self.shards.len()
+    }
+
+    pub fn len(&self) -> usize{
+        self.shards.iter().map(|s| s.lock().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.shards.iter().all(|s| s.lock().unwrap().is_empty())
+    }
+
+    pub fn insert(&self, key:K, val:V) -> Option<V>{
+        self.shard(&key).insert(key, val)
+    }
+
+    pub fn remove(&self, key:&K) -> Option<V>{
+        self.shard(key).remove(key)
+    }
+
+    pub fn contains(&self, key:&K) -> bool{
+        self.shard(key).contains(key)
+    }
+
+    // 在锁内访问值，避免克隆
+    pub fn get_with<R, F: FnOnce(&V) -> R>(&self, key:&K, f:F) -> Option<R>{
+        self.shard(key).get(key).map(f)
+    }
+
+    pub fn clear(&self){
+        for s in self.shards.iter() {
+            s.lock().unwrap().clear();
+        }
+    }
+}
+
+impl<K: Clone + Hash + Eq, V: Clone> ShardedCache<K, V> {
+    pub fn get(&self, key:&K) -> Option<V>{
+        self.shard(key).get(key).cloned()
+    }
+
+    pub fn peek(&self, key:&K) -> Option<V>{
+        self.shard(key).peek(key).cloned()
+    }
+
+    // 读取数据，未命中时调用 f 计算并插入，同一个键同时只有一个线程执行 f
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&self, key:K, f:F) -> V{
+        match self.try_get_or_insert_with(key, || Ok::<V, ()>(f())) {
+            Ok(v) => v,
+            Err(()) => unreachable!(),
+        }
+    }
+
+    // 同 get_or_insert_with，f 失败时不缓存，等待的线程会重新尝试加载
+    pub fn try_get_or_insert_with<E, F>(&self, key:K, f:F) -> Result<V, E>
+        where F: FnOnce() -> Result<V, E> {
+        if let Some(v) = self.get(&key) {
+            return Ok(v);
+        }
+
+        loop {
+            let pending = {
+                let mut loading = self.loading.lock().unwrap();
+                // 拿到锁之前别的线程可能已经加载完成
+                if let Some(v) = self.peek(&key) {
+                    return Ok(v);
+                }
+                match loading.get(&key) {
+                    Some(p) => Some(p.clone()),
+                    None => {
+                        let p = Arc::new(Pending{ state:Mutex::new((false, None)), cond:Condvar::new() });
+                        loading.insert(key.clone(), p.clone());
+                        drop(loading);
+
+                        // 由当前线程加载
+                        let guard = LoadGuard{ loading:&self.loading, key:&key, pending:p };
+                        let val = f()?;
+                        self.insert(key.clone(), val.clone());
+                        guard.pending.state.lock().unwrap().1 = Some(val.clone());
+                        return Ok(val);
+                    },
+                }
+            };
+
+            // 等待其他线程加载完成，加载失败就重新来过
+            if let Some(p) = pending {
+                let mut state = p.state.lock().unwrap();
+                while !state.0 {
+                    state = p.cond.wait(state).unwrap();
+                }
+                if let Some(v) = state.1.clone() {
+                    return Ok(v);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn it_works() {
+        assert_send_sync::<ShardedCache<String, Vec<u8>>>();
+
+        let cache = ShardedCache::with_shards(10, 3);
+        for i in 0..100 {
+            cache.insert(i, i * 2);
+        }
+        assert!(cache.len() <= 10);
+        assert_eq!(cache.get(&99), Some(198));
+        assert_eq!(cache.get_with(&99, |v| v + 1), Some(199));
+        assert_eq!(cache.remove(&99), Some(198));
+        assert!(!cache.contains(&99));
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_small_capacity() {
+        // 容量小于分片数时不会有容量为 0 的分片，放得下 cap 个数据项
+        let cache = ShardedCache::new(4);
+        assert_eq!(cache.shard_count(), 1);
+        for i in 0..4 {
+            cache.insert(i, i);
+        }
+        assert_eq!(cache.len(), 4);
+        cache.insert(4, 4);
+        assert_eq!(cache.len(), 4);
+        assert!(!cache.contains(&0));
+
+        assert_eq!(ShardedCache::<u32, u32>::with_shards(100, 8).shard_count(), 6);
+        assert_eq!(ShardedCache::<u32, u32>::with_shards(1000, 8).shard_count(), 8);
+        assert_eq!(ShardedCache::<u32, u32>::new(0).shard_count(), 1);
+    }
+
+    #[test]
+    fn test_stress_no_loss() {
+        // 容量足够时（每个分片都放得下全部数据），并发写入的数据一个都不能丢
+        let cache = Arc::new(ShardedCache::with_shards(4 * 8000, 4));
+        let handles: Vec<_> = (0..8).map(|t| {
+            let cache = cache.clone();
+            thread::spawn(move || {
+                for i in 0..1000 {
+                    let key = t * 1000 + i;
+                    cache.insert(key, key * 3);
+                    assert_eq!(cache.get(&key), Some(key * 3));
+                }
+            })
+        }).collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        assert_eq!(cache.len(), 8000);
+        for key in 0..8000 {
+            assert_eq!(cache.get(&key), Some(key * 3));
+        }
+    }
+
+    #[test]
+    fn test_stress_bounded() {
+        // 容量不足时总数不超过容量，读到的值必须和键对应
+        let cache = Arc::new(ShardedCache::with_shards(100, 8));
+        let handles: Vec<_> = (0..8).map(|t| {
+            let cache = cache.clone();
+            thread::spawn(move || {
+                for i in 0..5000 {
+                    let key = (t * 7919 + i * 31) % 1000;
+                    cache.insert(key, key.to_string());
+                    if let Some(v) = cache.get(&((key + 1) % 1000)) {
+                        assert_eq!(v, ((key + 1) % 1000).to_string());
+                    }
+                    assert!(cache.len() <= 100);
+                }
+            })
+        }).collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert!(cache.len() <= 100);
+    }
+
+    #[test]
+    fn test_coalesced_load() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        // 16 个线程同时读取同一个未命中的键，加载只执行一次
+        let cache = Arc::new(ShardedCache::new(100));
+        let loads = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..16).map(|_| {
+            let cache = cache.clone();
+            let loads = loads.clone();
+            thread::spawn(move || {
+                cache.get_or_insert_with("key", || {
+                    loads.fetch_add(1, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(50));
+                    42
+                })
+            })
+        }).collect();
+        for h in handles {
+            assert_eq!(h.join().unwrap(), 42);
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.get(&"key"), Some(42));
+
+        // 加载失败不缓存，下一次重新加载
+        assert_eq!(cache.try_get_or_insert_with("bad", || Err("db down")), Err("db down"));
+        assert_eq!(cache.try_get_or_insert_with("bad", || Ok::<_, ()>(7)), Ok(7));
+    }
+}
@@ -0,0 +1,499 @@
+// 内存 + 磁盘两级缓存
+// 内存层是 LRU_Cache，从内存层淘汰的数据项降级到磁盘层；磁盘层也是 LRU，
+// 每个值单独存成目录下的一个文件，总字节数不超过预算，超出时删除最久未使用的文件。
+// 磁盘层命中时把数据项升级回内存层。值用 serde/bincode 序列化，
+// 磁盘层的索引（键 -> 文件）只在 flush 或 drop 时写入 index.bin，重启后重新打开目录即可恢复。
+// 内存层的数据不会自动写盘，关闭前要调用 persist，否则只能恢复已经降级到磁盘层的数据项
+
+use std::fs;
+use std::io;
+use std::hash::Hash;
+use std::collections::HashSet;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use crate::LRU::lru::{LRU_Cache, Fetched, SnapshotError};
+
+// 索引文件名
+const INDEX_FILE:&str = "index.bin";
+
+// 值文件的扩展名
+const VALUE_EXT:&str = "val";
+
+// 读写磁盘层的错误
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum TierError {
+    Decode,                     // 索引或值文件无法解析
+    Io(io::ErrorKind),          // 读写文件失败
+}
+
+impl From<io::Error> for TierError {
+    fn from(e:io::Error) -> Self {
+        TierError::Io(e.kind())
+    }
+}
+
+impl From<SnapshotError> for TierError {
+    fn from(e:SnapshotError) -> Self {
+        match e {
+            SnapshotError::Decode => TierError::Decode,
+            SnapshotError::Io(kind) => TierError::Io(kind),
+        }
+    }
+}
+
+// 磁盘 LRU 缓存，索引中保存 键 -> (文件编号, 字节数)
+pub struct DiskCache<K: Clone + Hash + Eq + Serialize, V> {
+    dir:PathBuf,
+    max_bytes:u64,
+    bytes:u64,
+    next_id:u64,
+    index:LRU_Cache<K, (u64, u64)>,
+    _val:PhantomData<V>,
+}
+
+impl<K, V> DiskCache<K, V>
+    where K: Clone + Hash + Eq + Serialize + DeserializeOwned, V: Serialize + DeserializeOwned {
+    // 打开目录，目录中已有索引时恢复索引。
+    // 索引中找不到文件的数据项被丢弃，不在索引中的值文件被删除
+    pub fn open<P: AsRef<Path>>(dir:P, max_bytes:u64) -> Result<Self, TierError>{
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        // 数量不设上限，只由字节数限制
+        let mut index: LRU_Cache<K, (u64, u64)> = LRU_Cache::unbounded();
+        let index_path = dir.join(INDEX_FILE);
+        if index_path.exists() {
+            index.load_snapshot(&index_path)?;
+        }
+
+        let mut cache = DiskCache{ dir, max_bytes, bytes:0, next_id:0, index, _val:PhantomData };
+        let missing: Vec<K> = cache.index.iter()
+            .filter(|&(_, &(id, _))| !cache.path(id).is_file())
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in missing.iter() {
+            cache.index.remove(key);
+        }
+        for (_, &(id, size)) in cache.index.iter() {
+            cache.bytes += size;
+            cache.next_id = cache.next_id.max(id + 1);
+        }
+
+        let ids: HashSet<u64> = cache.index.iter().map(|(_, &(id, _))| id).collect();
+        for entry in fs::read_dir(&cache.dir)? {
+            let path = entry?.path();
+            if path.extension().map_or(false, |e| e == VALUE_EXT) {
+                let id = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse().ok());
+                if !id.map_or(false, |id| ids.contains(&id)) {
+                    fs::remove_file(&path)?;
+                }
+            }
+        }
+
+        // 预算变小时按新的预算淘汰
+        cache.evict()?;
+        Ok(cache)
+    }
+
+    fn path(&self, id:u64) -> PathBuf{
+        self.dir.join(format!("{}.{}", id, VALUE_EXT))
+    }
+
+    pub fn len(&self) -> usize{
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.index.is_empty()
+    }
+
+    // 当前占用的字节数
+    pub fn bytes(&self) -> u64{
+        self.bytes
+    }
+
+    pub fn max_bytes(&self) -> u64{
+        self.max_bytes
+    }
+
+    pub fn contains(&self, key:&K) -> bool{
+        self.index.contains(key)
+    }
+
+    // 超出预算时删除最久未使用的文件
+    fn evict(&mut self) -> Result<(), TierError>{
+        while self.bytes > self.max_bytes {
+            match self.index.pop_lru() {
+                Some((_, (id, size))) => {
+                    self.bytes -= size;
+                    fs::remove_file(self.path(id))?;
+                },
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    // 写入数据项，返回是否写入；比整个预算还大的值不写入，旧值也一起删除。
+    // 新值先写到新编号的文件，再替换索引、删除旧文件，写入失败时旧值仍然可读
+    pub fn put(&mut self, key:K, val:&V) -> Result<bool, TierError>{
+        let bytes = bincode::serialize(val).map_err(|_| TierError::Decode)?;
+        let size = bytes.len() as u64;
+        if size > self.max_bytes {
+            self.remove(&key)?;
+            return Ok(false);
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let path = self.path(id);
+        if let Err(e) = fs::write(&path, &bytes) {
+            let _ = fs::remove_file(&path);
+            return Err(e.into());
+        }
+        self.bytes += size;
+        if let Some((old, old_size)) = self.index.insert_silent(key, (id, size)) {
+            self.bytes -= old_size;
+            fs::remove_file(self.path(old))?;
+        }
+        self.evict()?;
+        Ok(true)
+    }
+
+    // 读取数据项，提升为最近使用
+    pub fn get(&mut self, key:&K) -> Result<Option<V>, TierError>{
+        let (id, _) = match self.index.get(key) {
+            Some(&entry) => entry,
+            None => return Ok(None),
+        };
+        let bytes = fs::read(self.path(id))?;
+        bincode::deserialize(&bytes).map(Some).map_err(|_| TierError::Decode)
+    }
+
+    // 读出并删除数据项
+    pub fn take(&mut self, key:&K) -> Result<Option<V>, TierError>{
+        let val = self.get(key)?;
+        self.remove(key)?;
+        Ok(val)
+    }
+
+    // 删除数据项，返回是否存在
+    pub fn remove(&mut self, key:&K) -> Result<bool, TierError>{
+        match self.index.remove_silent(key) {
+            Some((id, size)) => {
+                self.bytes -= size;
+                fs::remove_file(self.path(id))?;
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    pub fn clear(&mut self) -> Result<(), TierError>{
+        while let Some((_, (id, _))) = self.index.pop_lru() {
+            fs::remove_file(self.path(id))?;
+        }
+        self.bytes = 0;
+        self.flush()
+    }
+}
+
+impl<K: Clone + Hash + Eq + Serialize, V> DiskCache<K, V> {
+    // 把索引写入磁盘，先写临时文件再改名，避免留下写了一半的索引
+    pub fn flush(&self) -> Result<(), TierError>{
+        let tmp = self.dir.join(format!("{}.tmp", INDEX_FILE));
+        self.index.save_snapshot(&tmp)?;
+        fs::rename(&tmp, self.dir.join(INDEX_FILE))?;
+        Ok(())
+    }
+}
+
+impl<K: Clone + Hash + Eq + Serialize, V> Drop for DiskCache<K, V> {
+    fn drop(&mut self){
+        let _ = self.flush();
+    }
+}
+
+// 两级缓存
+pub struct TieredCache<K: Clone + Hash + Eq + Serialize, V> {
+    memory:LRU_Cache<K, V>,
+    disk:DiskCache<K, V>,
+}
+
+impl<K, V> TieredCache<K, V>
+    where K: Clone + Hash + Eq + Serialize + DeserializeOwned, V: Serialize + DeserializeOwned {
+    // 内存层最多 cap 项，磁盘层放在 dir 目录下，最多 max_bytes 字节
+    pub fn open<P: AsRef<Path>>(cap:usize, dir:P, max_bytes:u64) -> Result<Self, TierError>{
+        Ok(TieredCache{
+            memory:LRU_Cache::with_capacity(cap),
+            disk:DiskCache::open(dir, max_bytes)?,
+        })
+    }
+
+    // 两层的数据项总数
+    pub fn len(&self) -> usize{
+        self.memory.len() + self.disk.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.memory.is_empty() && self.disk.is_empty()
+    }
+
+    pub fn contains(&self, key:&K) -> bool{
+        self.memory.contains(key) || self.disk.contains(key)
+    }
+
+    pub fn memory(&self) -> &LRU_Cache<K, V>{
+        &self.memory
+    }
+
+    pub fn disk(&self) -> &DiskCache<K, V>{
+        &self.disk
+    }
+
+    // 放入内存层，内存层满了就把最久未使用的降级到磁盘层。
+    // 先写入磁盘层再从内存层移除，写入失败时数据项仍留在内存层
+    fn put_memory(&mut self, key:K, val:V) -> Result<(), TierError>{
+        if self.memory.is_full() && !self.memory.contains(&key) {
+            if let Some((k, v)) = self.memory.peek_lru() {
+                self.disk.put(k.clone(), v)?;
+                self.memory.pop_lru();
+            }
+        }
+        self.memory.insert(key, val);
+        Ok(())
+    }
+
+    pub fn insert(&mut self, key:K, val:V) -> Result<(), TierError>{
+        if self.memory.cap() == 0 {
+            // 由磁盘层自己替换旧值
+            self.disk.put(key, &val)?;
+            return Ok(());
+        }
+        // 新值放入内存层后再删除磁盘层的旧值，降级失败时旧值仍然保留
+        self.put_memory(key.clone(), val)?;
+        self.disk.remove(&key)?;
+        Ok(())
+    }
+
+    // 读取数据项，磁盘层命中时升级到内存层；内存层容量为 0 时值留在磁盘层，直接返回读出的值
+    pub fn get(&mut self, key:&K) -> Result<Option<Fetched<'_, V>>, TierError>{
+        if self.memory.contains(key) {
+            return Ok(self.memory.get(key).map(Fetched::Cached));
+        }
+        let val = match self.disk.get(key)? {
+            Some(val) => val,
+            None => return Ok(None),
+        };
+        if self.memory.cap() == 0 {
+            return Ok(Some(Fetched::Computed(val)));
+        }
+        // 升级成功后再从磁盘层删除，中途失败也不会丢失数据项
+        self.put_memory(key.clone(), val)?;
+        self.disk.remove(key)?;
+        Ok(self.memory.get(key).map(Fetched::Cached))
+    }
+
+    pub fn remove(&mut self, key:&K) -> Result<Option<V>, TierError>{
+        match self.memory.remove_silent(key) {
+            Some(val) => {
+                self.disk.remove(key)?;
+                Ok(Some(val))
+            },
+            None => self.disk.take(key),
+        }
+    }
+
+    pub fn clear(&mut self) -> Result<(), TierError>{
+        self.memory.clear();
+        self.disk.clear()
+    }
+
+    // 把内存层的数据全部写到磁盘层，并保存索引，用于关闭前持久化。
+    // 从最久未使用的开始写，写入失败时没写完的数据项仍留在内存层
+    pub fn persist(&mut self) -> Result<(), TierError>{
+        while let Some((key, val)) = self.memory.peek_lru() {
+            self.disk.put(key.clone(), val)?;
+            self.memory.pop_lru();
+        }
+        self.disk.flush()
+    }
+
+    // 保存磁盘层的索引，不包括内存层的数据，需要持久化内存层时用 persist
+    pub fn flush(&self) -> Result<(), TierError>{
+        self.disk.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name:&str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    // 目录中值文件的个数
+    fn value_files(dir:&Path) -> usize {
+        fs::read_dir(dir).unwrap()
+            .filter(|e| e.as_ref().unwrap().path().extension().map_or(false, |e| e == VALUE_EXT))
+            .count()
+    }
+
+    #[test]
+    fn it_works() {
+        let dir = test_dir("rust_studying_test_tiered");
+        let mut cache = TieredCache::open(2, &dir, 1 << 20).unwrap();
+        cache.insert(1, "a".to_string()).unwrap();
+        cache.insert(2, "b".to_string()).unwrap();
+        cache.insert(3, "c".to_string()).unwrap();
+
+        // 1 被降级到磁盘
+        assert!(!cache.memory().contains(&1) && cache.disk().contains(&1));
+        assert_eq!(cache.len(), 3);
+
+        // 磁盘命中后升级回内存，2 被降级
+        assert_eq!(cache.get(&1).unwrap().as_deref(), Some(&"a".to_string()));
+        assert!(cache.memory().contains(&1));
+        assert!(cache.disk().contains(&2) && !cache.disk().contains(&1));
+        assert_eq!(value_files(&dir), 1);
+
+        // 新值使磁盘上的旧值作废
+        cache.insert(2, "bb".to_string()).unwrap();
+        assert!(!cache.disk().contains(&2));
+        assert_eq!(cache.remove(&3), Ok(Some("c".to_string())));
+        assert_eq!(cache.get(&2).unwrap().as_deref(), Some(&"bb".to_string()));
+        assert_eq!(cache.get(&9), Ok(None));
+
+        cache.clear().unwrap();
+        assert!(cache.is_empty());
+        assert_eq!(value_files(&dir), 0);
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_zero_memory() {
+        // 内存层容量为 0 时数据项都在磁盘层，读取仍然返回值
+        let dir = test_dir("rust_studying_test_tiered_zero");
+        let mut cache = TieredCache::open(0, &dir, 1 << 20).unwrap();
+        cache.insert(1, "a".to_string()).unwrap();
+        assert_eq!(cache.get(&1), Ok(Some(Fetched::Computed("a".to_string()))));
+        assert_eq!(cache.get(&1).unwrap().as_deref(), Some(&"a".to_string()));
+        assert!(cache.contains(&1) && cache.memory().is_empty());
+        assert_eq!(cache.get(&2), Ok(None));
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_failure() {
+        // 降级时写磁盘失败，被降级的数据项留在内存层
+        let dir = test_dir("rust_studying_test_tiered_failure");
+        let mut cache = TieredCache::open(1, &dir, 1 << 20).unwrap();
+        cache.insert(1, "a".to_string()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(cache.insert(2, "b".to_string()).is_err());
+        assert_eq!(cache.get(&1).unwrap().as_deref(), Some(&"a".to_string()));
+        assert!(cache.persist().is_err());
+        assert!(cache.memory().contains(&1));
+
+        fs::create_dir_all(&dir).unwrap();
+        cache.persist().unwrap();
+        assert!(cache.memory().is_empty() && cache.disk().contains(&1));
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replace_failure() {
+        // 新值写入失败时旧值仍然可读：在新文件的位置放一个目录，root 也无法写入
+        let dir = test_dir("rust_studying_test_tiered_replace");
+        let mut disk = DiskCache::open(&dir, 1 << 20).unwrap();
+        disk.put(1u32, &"old".to_string()).unwrap();
+        let blocked = disk.path(disk.next_id);
+        fs::create_dir(&blocked).unwrap();
+        assert!(disk.put(1, &"new".to_string()).is_err());
+        assert_eq!(disk.get(&1), Ok(Some("old".to_string())));
+        assert_eq!(disk.bytes(), 11);
+        fs::remove_dir(&blocked).unwrap();
+        disk.put(1, &"new".to_string()).unwrap();
+        assert_eq!(disk.get(&1), Ok(Some("new".to_string())));
+        assert_eq!(value_files(&dir), 1);
+        drop(disk);
+        fs::remove_dir_all(&dir).unwrap();
+
+        // 插入时降级失败，磁盘层的旧值不会丢失
+        let dir = test_dir("rust_studying_test_tiered_insert");
+        let mut cache = TieredCache::open(1, &dir, 1 << 20).unwrap();
+        cache.insert(2u32, "old".to_string()).unwrap();
+        cache.insert(1, "a".to_string()).unwrap();
+        fs::create_dir(cache.disk.path(cache.disk.next_id)).unwrap();
+        assert!(cache.insert(2, "new".to_string()).is_err());
+        assert_eq!(cache.get(&2).unwrap().as_deref(), Some(&"old".to_string()));
+        assert_eq!(cache.get(&1).unwrap().as_deref(), Some(&"a".to_string()));
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_byte_budget() {
+        let dir = test_dir("rust_studying_test_tiered_budget");
+        // 每个值 8 字节长度前缀 + 100 字节
+        let mut disk = DiskCache::open(&dir, 1000).unwrap();
+        for i in 0..20u32 {
+            assert_eq!(disk.put(i, &vec![i as u8; 100]), Ok(true));
+            assert!(disk.bytes() <= 1000);
+        }
+        assert_eq!(disk.len(), 9);
+        assert_eq!(value_files(&dir), 9);
+        assert!(!disk.contains(&10) && disk.contains(&11));
+
+        // 读取后变为最近使用，不会先被淘汰
+        assert_eq!(disk.get(&11), Ok(Some(vec![11u8; 100])));
+        disk.put(20, &vec![0; 100]).unwrap();
+        assert!(disk.contains(&11) && !disk.contains(&12));
+
+        // 超过预算的值不写入
+        assert_eq!(disk.put(21, &vec![0; 2000]), Ok(false));
+        assert!(!disk.contains(&21));
+        drop(disk);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_restart() {
+        let dir = test_dir("rust_studying_test_tiered_restart");
+        {
+            let mut cache = TieredCache::open(2, &dir, 1 << 20).unwrap();
+            for i in 0..5u32 {
+                cache.insert(i, i.to_string()).unwrap();
+            }
+            cache.persist().unwrap();
+        }
+
+        // 遗留的孤立文件在重新打开时被删除
+        fs::write(dir.join(format!("999.{}", VALUE_EXT)), b"junk").unwrap();
+
+        let mut cache: TieredCache<u32, String> = TieredCache::open(2, &dir, 1 << 20).unwrap();
+        assert_eq!(cache.disk().len(), 5);
+        assert_eq!(value_files(&dir), 5);
+        for i in 0..5u32 {
+            assert_eq!(cache.get(&i).unwrap().as_deref(), Some(&i.to_string()));
+        }
+        assert_eq!(cache.len(), 5);
+
+        // 预算缩小后重新打开，只保留最近使用的
+        cache.persist().unwrap();
+        drop(cache);
+        let disk: DiskCache<u32, String> = DiskCache::open(&dir, 20).unwrap();
+        assert!(disk.bytes() <= 20);
+        assert!(disk.contains(&4));
+        drop(disk);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
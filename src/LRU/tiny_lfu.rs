@@ -0,0 +1,337 @@
+// W-TinyLFU 缓存（Caffeine 使用的策略）
+// 新数据先进入一个很小的窗口 LRU（约 1% 容量），从窗口淘汰出来的候选者
+// 要和主缓存的淘汰对象比较访问频率，频率更高才能进入主缓存，否则直接丢弃。
+// 主缓存是分段 LRU：probation 保存只在主缓存中被访问过一次的数据，
+// 再次命中后升级到 protected（约 80% 主缓存容量）。
+// 访问频率由 Count-Min Sketch 估计，计数到达采样上限后全部减半，让旧的热点逐渐冷却
+
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+use crate::LRU::lru::LRU_Cache;
+use crate::LRU::policy::CachePolicy;
+
+// Sketch 的行数
+const DEPTH:usize = 4;
+
+// 计数上限，和 4 位计数器一致
+const MAX_COUNT:u8 = 15;
+
+// 每行使用不同的种子
+const SEEDS:[u64;DEPTH] = [
+    0xc3a5c85c97cb3127, 0xb492b66fbe98f273, 0x9ae16a3b2f90404f, 0xcbf29ce484222325,
+];
+
+// Count-Min Sketch，估计的频率不会低于真实值
+pub struct CountMinSketch {
+    table:Vec<u8>,
+    mask:usize,
+    additions:usize,
+    sample_size:usize,          // 累计计数到达这个值后全部减半
+}
+
+impl CountMinSketch {
+    // 为大约 cap 个不同的键准备
+    pub fn new(cap:usize) -> Self{
+        let width = cap.max(1).next_power_of_two();
+        CountMinSketch{
+            table:vec![0; width * DEPTH],
+            mask:width - 1,
+            additions:0,
+            sample_size:10 * cap.max(1),
+        }
+    }
+
+    fn index(&self, hash:u64, row:usize) -> usize{
+        let h = (hash ^ SEEDS[row]).wrapping_mul(0x9e3779b97f4a7c15);
+        row * (self.mask + 1) + ((h >> 32) as usize & self.mask)
+    }
+
+    pub fn increment(&mut self, hash:u64){
+        for row in 0..DEPTH {
+            let i = self.index(hash, row);
+            if self.table[i] < MAX_COUNT {
+                self.table[i] += 1;
+            }
+        }
+        self.additions += 1;
+        if self.additions >= self.sample_size {
+            self.reset();
+        }
+    }
+
+    pub fn frequency(&self, hash:u64) -> u8{
+        (0..DEPTH).map(|row| self.table[self.index(hash, row)]).min().unwrap()
+    }
+
+    // 老化：所有计数减半
+    fn reset(&mut self){
+        for c in self.table.iter_mut() {
+            *c >>= 1;
+        }
+        self.additions /= 2;
+    }
+}
+
+#[allow(non_camel_case_types)]
+pub struct TinyLFU_Cache<K, V> {
+    cap:usize,
+    main_cap:usize,
+    protected_cap:usize,
+    window:LRU_Cache<K, V>,
+    probation:LRU_Cache<K, V>,
+    protected:LRU_Cache<K, V>,
+    sketch:CountMinSketch,
+}
+
+fn hash_key<K: Hash>(key:&K) -> u64{
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<K: Clone + Hash + Eq, V> TinyLFU_Cache<K, V> {
+    pub fn with_capacity(cap:usize) -> Self{
+        let window_cap = if cap == 0 { 0 } else { (cap / 100).max(1) };
+        let main_cap = cap - window_cap;
+        let protected_cap = main_cap * 8 / 10;
+        TinyLFU_Cache{
+            cap,
+            main_cap,
+            protected_cap,
+            window:LRU_Cache::with_capacity(window_cap),
+            probation:LRU_Cache::with_capacity(main_cap),
+            protected:LRU_Cache::with_capacity(protected_cap),
+            sketch:CountMinSketch::new(cap),
+        }
+    }
+
+    pub fn len(&self) -> usize{
+        self.window.len() + self.probation.len() + self.protected.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+
+    pub fn cap(&self) -> usize{
+        self.cap
+    }
+
+    // 估计的访问频率
+    pub fn frequency(&self, key:&K) -> u8{
+        self.sketch.frequency(hash_key(key))
+    }
+
+    // probation 中的数据再次命中，升级到 protected，protected 满了就把最久未使用的降级
+    fn promote(&mut self, key:&K){
+        if let Some(val) = self.probation.remove(key) {
+            if self.protected_cap == 0 {
+                self.probation.insert(key.clone(), val);
+                return;
+            }
+            if self.protected.is_full() {
+                if let Some((k, v)) = self.protected.pop_lru() {
+                    self.probation.insert(k, v);
+                }
+            }
+            self.protected.insert(key.clone(), val);
+        }
+    }
+
+    // 从窗口淘汰出来的候选者尝试进入主缓存
+    fn admit(&mut self, key:K, val:V){
+        if self.probation.len() + self.protected.len() < self.main_cap {
+            self.probation.insert(key, val);
+            return;
+        }
+
+        let victim = match self.probation.peek_lru().or_else(|| self.protected.peek_lru()) {
+            Some((k, _)) => hash_key(k),
+            None => return,
+        };
+        if self.sketch.frequency(hash_key(&key)) > self.sketch.frequency(victim) {
+            if self.probation.pop_lru().is_none() {
+                self.protected.pop_lru();
+            }
+            self.probation.insert(key, val);
+        }
+    }
+
+    pub fn insert(&mut self, key:K, val:V) -> Option<V>{
+        if self.cap == 0 {
+            return None;
+        }
+        self.sketch.increment(hash_key(&key));
+
+        if self.window.contains(&key) {
+            return self.window.insert(key, val);
+        }
+        if self.probation.contains(&key) {
+            self.promote(&key);
+        }
+        if let Some(old) = self.probation.get_mut(&key).or_else(|| self.protected.get_mut(&key)) {
+            return Some(std::mem::replace(old, val));
+        }
+
+        // 新数据进入窗口，窗口满了就把最久未使用的交给主缓存
+        if self.window.is_full() {
+            if let Some((k, v)) = self.window.pop_lru() {
+                self.admit(k, v);
+            }
+        }
+        self.window.insert(key, val);
+        None
+    }
+
+    pub fn get(&mut self, key:&K) -> Option<&V>{
+        self.sketch.increment(hash_key(key));
+        if self.window.contains(key) {
+            return self.window.get(key);
+        }
+        if self.probation.contains(key) {
+            self.promote(key);
+            if self.probation.contains(key) {
+                return self.probation.get(key);
+            }
+        }
+        self.protected.get(key)
+    }
+
+    pub fn peek(&self, key:&K) -> Option<&V>{
+        self.window.peek(key)
+            .or_else(|| self.probation.peek(key))
+            .or_else(|| self.protected.peek(key))
+    }
+
+    pub fn remove(&mut self, key:&K) -> Option<V>{
+        self.window.remove(key)
+            .or_else(|| self.probation.remove(key))
+            .or_else(|| self.protected.remove(key))
+    }
+
+    pub fn contains(&self, key:&K) -> bool{
+        self.window.contains(key) || self.probation.contains(key) || self.protected.contains(key)
+    }
+
+    // 清空数据，频率统计保留
+    pub fn clear(&mut self){
+        self.window.clear();
+        self.probation.clear();
+        self.protected.clear();
+    }
+}
+
+impl<K: Clone + Hash + Eq, V> CachePolicy<K, V> for TinyLFU_Cache<K, V> {
+    fn insert(&mut self, key:K, val:V) -> Option<V>{
+        TinyLFU_Cache::insert(self, key, val)
+    }
+
+    fn get(&mut self, key:&K) -> Option<&V>{
+        TinyLFU_Cache::get(self, key)
+    }
+
+    fn peek(&self, key:&K) -> Option<&V>{
+        TinyLFU_Cache::peek(self, key)
+    }
+
+    fn remove(&mut self, key:&K) -> Option<V>{
+        TinyLFU_Cache::remove(self, key)
+    }
+
+    fn contains(&self, key:&K) -> bool{
+        TinyLFU_Cache::contains(self, key)
+    }
+
+    fn len(&self) -> usize{
+        TinyLFU_Cache::len(self)
+    }
+
+    fn cap(&self) -> usize{
+        TinyLFU_Cache::cap(self)
+    }
+
+    fn clear(&mut self){
+        TinyLFU_Cache::clear(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use crate::LRU::policy::hit_ratio;
+
+    // 生成服从 Zipf 分布的访问记录
+    fn zipf_trace(items:usize, s:f64, len:usize, seed:u64) -> Vec<usize> {
+        let mut cdf = Vec::with_capacity(items);
+        let mut sum = 0.0;
+        for k in 1..=items {
+            sum += 1.0 / (k as f64).powf(s);
+            cdf.push(sum);
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..len).map(|_| {
+            let u = rng.gen::<f64>() * sum;
+            cdf.partition_point(|&c| c < u)
+        }).collect()
+    }
+
+    #[test]
+    fn test_sketch() {
+        let mut sketch = CountMinSketch::new(16);
+        for _ in 0..5 {
+            sketch.increment(42);
+        }
+        sketch.increment(7);
+        assert!(sketch.frequency(42) >= 5);
+        assert!(sketch.frequency(7) >= 1);
+
+        // 老化后计数减半
+        let before = sketch.frequency(42);
+        sketch.reset();
+        assert_eq!(sketch.frequency(42), before / 2);
+
+        // 采样到上限时自动老化
+        let mut sketch = CountMinSketch::new(4);
+        for _ in 0..39 {
+            sketch.increment(42);
+        }
+        assert_eq!(sketch.frequency(42), MAX_COUNT);
+        sketch.increment(42);
+        assert_eq!(sketch.frequency(42), MAX_COUNT / 2);
+    }
+
+    #[test]
+    fn it_works() {
+        let mut cache = TinyLFU_Cache::with_capacity(100);
+        for i in 0..100 {
+            cache.insert(i, i);
+        }
+        assert_eq!(cache.len(), 100);
+        assert_eq!(cache.get(&50), Some(&50));
+        assert_eq!(cache.insert(50, 500), Some(50));
+        assert_eq!(cache.peek(&50), Some(&500));
+
+        // 热点数据被频繁访问后，不会被一次性的扫描挤出去
+        for _ in 0..5 {
+            for i in 0..10 {
+                cache.get(&i);
+            }
+        }
+        for i in 1000..2000 {
+            cache.insert(i, i);
+            assert!(cache.len() <= 100);
+        }
+        assert!((0..10).all(|i| cache.contains(&i)));
+        assert_eq!(cache.remove(&0), Some(0));
+    }
+
+    #[test]
+    fn test_zipf_hit_ratio() {
+        let trace = zipf_trace(10_000, 0.9, 200_000, 7);
+        let lru = hit_ratio(&mut LRU_Cache::with_capacity(500), &trace);
+        let tiny = hit_ratio(&mut TinyLFU_Cache::with_capacity(500), &trace);
+        assert!(tiny > lru + 0.05, "tinylfu {} lru {}", tiny, lru);
+    }
+}
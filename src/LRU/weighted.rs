@@ -0,0 +1,135 @@
+// 按权重限制容量的 LRU 缓存
+// 每个数据项的权重由用户提供的 weigher 计算（比如值的字节数），
+// 插入时从尾部淘汰直到放得下新数据项，比整个容量还重的数据项直接拒绝
+
+use std::hash::Hash;
+use crate::LRU::lru::{LRU_Cache, Iter};
+
+pub struct WeightedCache<K, V> {
+    cache:LRU_Cache<K, V>,
+    weigher:Box<dyn Fn(&K, &V) -> usize + Send + Sync>,
+    max_weight:usize,
+    weight:usize,
+}
+
+impl<K: Clone + Hash + Eq, V> WeightedCache<K, V> {
+    pub fn new<F>(max_weight:usize, weigher:F) -> Self
+        where F: Fn(&K, &V) -> usize + Send + Sync + 'static {
+        // 数量不设上限，只由权重限制
+        WeightedCache{
+            cache:LRU_Cache::unbounded(),
+            weigher:Box::new(weigher),
+            max_weight,
+            weight:0,
+        }
+    }
+
+    pub fn len(&self) -> usize{
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.cache.is_empty()
+    }
+
+    // 当前总权重
+    pub fn weight(&self) -> usize{
+        self.weight
+    }
+
+    pub fn max_weight(&self) -> usize{
+        self.max_weight
+    }
+
+    // 插入数据项，返回被替换的旧值；超过总容量的数据项原样返回 Err
+    pub fn insert(&mut self, key:K, val:V) -> Result<Option<V>, V>{
+        let w = (self.weigher)(&key, &val);
+        if w > self.max_weight {
+            return Err(val);
+        }
+
+        let old_val = self.remove(&key);
+        while self.weight + w > self.max_weight {
+            self.pop_lru();
+        }
+        self.weight += w;
+        self.cache.insert(key, val);
+        Ok(old_val)
+    }
+
+    pub fn get(&mut self, key:&K) -> Option<&V>{
+        self.cache.get(key)
+    }
+
+    pub fn peek(&self, key:&K) -> Option<&V>{
+        self.cache.peek(key)
+    }
+
+    pub fn peek_lru(&self) -> Option<(&K, &V)>{
+        self.cache.peek_lru()
+    }
+
+    pub fn contains(&self, key:&K) -> bool{
+        self.cache.contains(key)
+    }
+
+    pub fn remove(&mut self, key:&K) -> Option<V>{
+        let val = self.cache.remove_silent(key)?;
+        self.weight -= (self.weigher)(key, &val);
+        Some(val)
+    }
+
+    pub fn pop_lru(&mut self) -> Option<(K, V)>{
+        let (key, val) = self.cache.pop_lru()?;
+        self.weight -= (self.weigher)(&key, &val);
+        Some((key, val))
+    }
+
+    // 调整总容量，缩小时从尾部淘汰
+    pub fn resize(&mut self, max_weight:usize){
+        self.max_weight = max_weight;
+        while self.weight > max_weight {
+            self.pop_lru();
+        }
+    }
+
+    pub fn clear(&mut self){
+        self.cache.clear();
+        self.weight = 0;
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V>{
+        self.cache.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn it_works() {
+        let mut cache = WeightedCache::new(10, |_: &&str, v: &Vec<u8>| v.len());
+        assert_eq!(cache.insert("a", vec![0; 4]), Ok(None));
+        assert_eq!(cache.insert("b", vec![0; 4]), Ok(None));
+        assert_eq!(cache.weight(), 8);
+
+        // 放不下时从尾部淘汰，直到放得下
+        cache.get(&"a");
+        assert_eq!(cache.insert("c", vec![0; 3]), Ok(None));
+        assert!(!cache.contains(&"b"));
+        assert_eq!(cache.weight(), 7);
+
+        // 替换会重新计算权重
+        assert_eq!(cache.insert("a", vec![1; 7]), Ok(Some(vec![0; 4])));
+        assert_eq!(cache.weight(), 10);
+
+        // 比整个容量还重的直接拒绝，缓存不变
+        assert_eq!(cache.insert("d", vec![0; 11]), Err(vec![0; 11]));
+        assert_eq!(cache.len(), 2);
+
+        cache.resize(8);
+        assert_eq!(cache.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec!["a"]);
+        assert_eq!(cache.remove(&"a"), Some(vec![1; 7]));
+        assert_eq!(cache.weight(), 0);
+    }
+}
@@ -0,0 +1,64 @@
+// Base58 基准测试：比较按 u64 limb 转换的实现和逐字节转换的实现，
+// 输出 32 字节到 64 KiB 各种长度的数据的编解码吞吐量
+//
+// 用法：
+//   base58-bench [每项的最短测量时间，单位毫秒，默认 200]
+// 请用 --release 编译，逐字节实现在 64 KiB 时每次需要数秒
+
+use std::env;
+use std::hint::black_box;
+use std::process;
+use std::time::{Duration, Instant};
+use rand::{RngCore, SeedableRng};
+use rand::rngs::StdRng;
+use rust_studying::codec::base58::{self, Alphabet};
+
+const USAGE: &str = "usage: base58-bench [min-millis-per-case]";
+const SIZES: [usize; 6] = [32, 256, 1024, 4096, 16384, 65536];
+
+fn fail(msg:&str) -> ! {
+    eprintln!("error: {}", msg);
+    process::exit(1);
+}
+
+// 至少运行一次，直到总时间超过 min，返回每秒处理的 MiB 数
+fn throughput<F: FnMut()>(len:usize, min:Duration, mut f:F) -> f64 {
+    let start = Instant::now();
+    let mut runs = 0u32;
+    while runs == 0 || start.elapsed() < min {
+        f();
+        runs += 1;
+    }
+    let secs = start.elapsed().as_secs_f64() / runs as f64;
+    len as f64 / secs / (1024.0 * 1024.0)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let min = match args.as_slice() {
+        [] => Duration::from_millis(200),
+        [ms] => match ms.parse() {
+            Ok(ms) => Duration::from_millis(ms),
+            Err(_) => fail(&format!("invalid duration {}\n{}", ms, USAGE)),
+        },
+        _ => fail(USAGE),
+    };
+
+    let alphabet = Alphabet::BITCOIN;
+    let mut rng = StdRng::seed_from_u64(58);
+    println!("{:<6} {:>8} {:>14} {:>14} {:>8}", "op", "bytes", "limb MiB/s", "bytewise MiB/s", "speedup");
+    for len in SIZES {
+        let mut bytes = vec![0u8; len];
+        rng.fill_bytes(&mut bytes);
+        let text = base58::encode(&bytes, &alphabet);
+        assert_eq!(text, base58::encode_bytewise(&bytes, &alphabet));
+
+        let fast = throughput(len, min, || { black_box(base58::encode(black_box(&bytes), &alphabet)); });
+        let slow = throughput(len, min, || { black_box(base58::encode_bytewise(black_box(&bytes), &alphabet)); });
+        println!("{:<6} {:>8} {:>14.2} {:>14.2} {:>7.1}x", "encode", len, fast, slow, fast / slow);
+
+        let fast = throughput(len, min, || { black_box(base58::decode(black_box(&text), &alphabet).unwrap()); });
+        let slow = throughput(len, min, || { black_box(base58::decode_bytewise(black_box(&text), &alphabet).unwrap()); });
+        println!("{:<6} {:>8} {:>14.2} {:>14.2} {:>7.1}x", "decode", len, fast, slow, fast / slow);
+    }
+}
@@ -0,0 +1,67 @@
+// 缓存访问记录重放工具：用给定容量和淘汰策略重放访问记录，输出统计数据，
+// 用来根据线上的访问记录估计需要多大的缓存
+//
+// 用法：
+//   cache-replay <访问记录文件> <容量> [lru|lfu|arc|tinylfu|clock|clockpro|all]
+// 访问记录文件每行一个键，空行忽略
+
+use std::env;
+use std::fs;
+use std::process;
+use rust_studying::LRU::lru::LRU_Cache;
+use rust_studying::LRU::lfu::LFU_Cache;
+use rust_studying::LRU::arc::ARC_Cache;
+use rust_studying::LRU::tiny_lfu::TinyLFU_Cache;
+use rust_studying::LRU::clock::CLOCK_Cache;
+use rust_studying::LRU::clock_pro::CLOCKPro_Cache;
+use rust_studying::LRU::policy::{CachePolicy, replay};
+
+const USAGE: &str = "usage: cache-replay <trace-file> <capacity> [lru|lfu|arc|tinylfu|clock|clockpro|all]";
+const POLICIES: [&str; 6] = ["lru", "lfu", "arc", "tinylfu", "clock", "clockpro"];
+
+fn fail(msg:&str) -> ! {
+    eprintln!("error: {}", msg);
+    process::exit(1);
+}
+
+fn build(policy:&str, cap:usize) -> Box<dyn CachePolicy<String, ()>> {
+    match policy {
+        "lru" => Box::new(LRU_Cache::with_capacity(cap)),
+        "lfu" => Box::new(LFU_Cache::with_capacity(cap)),
+        "arc" => Box::new(ARC_Cache::with_capacity(cap)),
+        "tinylfu" => Box::new(TinyLFU_Cache::with_capacity(cap)),
+        "clock" => Box::new(CLOCK_Cache::with_capacity(cap)),
+        "clockpro" => Box::new(CLOCKPro_Cache::with_capacity(cap)),
+        _ => fail(&format!("unknown policy {}\n{}", policy, USAGE)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 2 || args.len() > 3 {
+        fail(USAGE);
+    }
+    let text = match fs::read_to_string(&args[0]) {
+        Ok(t) => t,
+        Err(e) => fail(&format!("unable to read {}: {}", args[0], e)),
+    };
+    let cap: usize = match args[1].parse() {
+        Ok(c) => c,
+        Err(_) => fail(&format!("invalid capacity {}", args[1])),
+    };
+    let policies: Vec<&str> = match args.get(2).map(|s| s.as_str()) {
+        None | Some("all") => POLICIES.to_vec(),
+        Some(p) => vec![p],
+    };
+
+    let trace: Vec<&str> = text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).collect();
+    println!("{:<8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>9}",
+        "policy", "capacity", "requests", "hits", "misses", "evictions", "hit%");
+    for policy in policies {
+        let mut cache = build(policy, cap);
+        let stats = replay(cache.as_mut(), trace.iter().map(|k| k.to_string()));
+        println!("{:<8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>8.2}%",
+            policy, cap, stats.requests(), stats.hits, stats.misses, stats.evictions,
+            stats.hit_ratio() * 100.0);
+    }
+}
@@ -0,0 +1,126 @@
+// 区块浏览器：打开保存在文件中的区块链，查看区块、交易和账户历史，并校验整条链
+//
+// 用法：
+//   chain-explorer <链文件> [--json] list
+//   chain-explorer <链文件> [--json] block <哈希|高度>
+//   chain-explorer <链文件> [--json] tx <交易哈希>
+//   chain-explorer <链文件> [--json] account <地址>
+//   chain-explorer <链文件> [--json] verify
+
+use std::env;
+use std::process;
+use serde::Serialize;
+use rust_studying::serializer::blockchain::{Blockchain,BlockView,TxView};
+
+const USAGE: &str = "usage: chain-explorer <chain-file> [--json] <list | block <hash|height> | tx <txs-hash> | account <address> | verify>";
+
+// 区块的单行摘要
+fn print_line(v:&BlockView) {
+    println!("{:>6}  {}  {}  {}", v.height, v.hash, v.time, v.tranxs.unwrap_or("<pruned>"));
+}
+
+// 区块的详细信息
+fn print_detail(v:&BlockView) {
+    println!("height:   {}", v.height);
+    println!("hash:     {}", v.hash);
+    println!("time:     {}", v.time);
+    println!("pre_hash: {}", v.pre_hash);
+    println!("txs_hash: {}", v.txs_hash);
+    println!("state:    {}", v.state_root);
+    println!("tranxs:   {}", v.tranxs.unwrap_or("<pruned>"));
+}
+
+// 交易的单行摘要
+fn print_tx(t:&TxView) {
+    println!("{:>6}  {}  {} -> {}  {}", t.height, t.block, t.from, t.to, t.amount);
+}
+
+fn fail(msg:&str) -> ! {
+    eprintln!("error: {}", msg);
+    process::exit(1);
+}
+
+fn print_json<T: Serialize>(value:&T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(s) => println!("{}", s),
+        Err(e) => fail(&e.to_string()),
+    }
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let json = match args.iter().position(|a| a == "--json") {
+        Some(i) => { args.remove(i); true },
+        None => false,
+    };
+    if args.len() < 2 {
+        fail(USAGE);
+    }
+
+    let bc = match Blockchain::load(&args[0]) {
+        Ok(bc) => bc,
+        Err(e) => fail(&format!("unable to open {}: {:?}", args[0], e)),
+    };
+
+    match (args[1].as_str(), args.get(2)) {
+        ("list", None) => {
+            let views = bc.block_info();
+            if json {
+                print_json(&views);
+            } else {
+                views.iter().for_each(print_line);
+            }
+        },
+        ("block", Some(id)) => {
+            // 先按高度解析，不是数字再按哈希查找
+            let found = match id.parse::<usize>() {
+                Ok(h) => bc.block_at(h).map(|b| (h, b)),
+                Err(_) => bc.find_block(id),
+            };
+            let view = match found {
+                Some((h, b)) => BlockView::new(h, b),
+                None => fail(&format!("block {} not found", id)),
+            };
+            if json { print_json(&view); } else { print_detail(&view); }
+        },
+        ("tx", Some(txs_hash)) => {
+            let view = match (bc.tx_info(txs_hash), bc.find_tx(txs_hash)) {
+                (Some(view), _) => view,
+                (None, Some((h, _))) => fail(&format!("transaction {} in block {} has been pruned", txs_hash, h)),
+                (None, None) => fail(&format!("transaction {} not found", txs_hash)),
+            };
+            if json { print_json(&view); } else { print_tx(&view); }
+        },
+        ("account", Some(addr)) => {
+            let history: Vec<TxView> = bc.account_history(addr).into_iter()
+                .filter_map(|(h, b)| TxView::new(h, b))
+                .collect();
+            if json {
+                print_json(&history);
+            } else {
+                history.iter().for_each(print_tx);
+            }
+        },
+        ("verify", None) => {
+            let result = bc.verify();
+            if json {
+                #[derive(Serialize)]
+                struct Verify { height:usize, valid:bool, error:Option<String> }
+                print_json(&Verify{
+                    height: bc.height(),
+                    valid: result.is_ok(),
+                    error: result.as_ref().err().map(|e| format!("{:?}", e)),
+                });
+            } else {
+                match &result {
+                    Ok(()) => println!("chain ok, height {}", bc.height()),
+                    Err(e) => println!("chain invalid: {:?}", e),
+                }
+            }
+            if result.is_err() {
+                process::exit(2);
+            }
+        },
+        _ => fail(USAGE),
+    }
+}
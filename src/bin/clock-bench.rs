@@ -0,0 +1,114 @@
+// CLOCK 与 LRU 的读多写少吞吐量基准测试
+// 访问的键有热点，读取未命中时写入，命中率约 85%，大部分操作只读。
+// 单线程时直接调用；多线程时 CLOCK 的 get 只需 &self，放在 RwLock 里用读锁并发读取，
+// LRU 的 get 要移动链表节点，只能放在 Mutex 里串行访问。输出每秒操作数和命中率
+//
+// 用法：
+//   clock-bench [线程数，默认 4] [每项的最短测量时间，单位毫秒，默认 500]
+// 请用 --release 编译
+
+use std::env;
+use std::hint::black_box;
+use std::process;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rust_studying::LRU::lru::LRU_Cache;
+use rust_studying::LRU::clock::CLOCK_Cache;
+
+const USAGE: &str = "usage: clock-bench [threads] [min-millis-per-case]";
+const CAPACITY: usize = 20_000;
+const KEYS: u64 = 40_000;
+const TRACE_LEN: usize = 1 << 20;
+
+fn fail(msg:&str) -> ! {
+    eprintln!("error: {}", msg);
+    process::exit(1);
+}
+
+// 有热点的访问记录，键越小越常被访问
+fn trace(seed:u64) -> Vec<u64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..TRACE_LEN).map(|_| (rng.gen::<f64>().powi(6) * KEYS as f64) as u64).collect()
+}
+
+// 各线程重放自己的访问记录，至少一遍，直到总时间超过 min，返回 (每秒操作数, 命中率)
+fn run<F>(threads:usize, min:Duration, access:F) -> (f64, f64)
+    where F: Fn(u64) -> bool + Send + Sync + 'static {
+    let access = Arc::new(access);
+    let start = Instant::now();
+    let handles: Vec<_> = (0..threads).map(|t| {
+        let access = access.clone();
+        thread::spawn(move || {
+            let trace = trace(t as u64);
+            let (mut ops, mut hits) = (0u64, 0u64);
+            let begin = Instant::now();
+            while ops == 0 || begin.elapsed() < min {
+                for &key in trace.iter() {
+                    hits += access(black_box(key)) as u64;
+                }
+                ops += trace.len() as u64;
+            }
+            (ops, hits)
+        })
+    }).collect();
+    let (ops, hits) = handles.into_iter()
+        .map(|h| h.join().unwrap())
+        .fold((0, 0), |(o, h), (ops, hits)| (o + ops, h + hits));
+    (ops as f64 / start.elapsed().as_secs_f64(), hits as f64 / ops as f64)
+}
+
+fn clock(threads:usize, min:Duration) -> (f64, f64) {
+    let cache = RwLock::new(CLOCK_Cache::with_capacity(CAPACITY));
+    run(threads, min, move |key| {
+        if cache.read().unwrap().get(&key).is_some() {
+            return true;
+        }
+        cache.write().unwrap().insert(key, key);
+        false
+    })
+}
+
+fn lru(threads:usize, min:Duration) -> (f64, f64) {
+    let cache = Mutex::new(LRU_Cache::with_capacity(CAPACITY));
+    run(threads, min, move |key| {
+        let mut cache = cache.lock().unwrap();
+        if cache.get(&key).is_some() {
+            return true;
+        }
+        cache.insert(key, key);
+        false
+    })
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let parse = |s:&String, what:&str| -> u64 {
+        match s.parse() {
+            Ok(n) if n > 0 => n,
+            _ => fail(&format!("invalid {} {}\n{}", what, s, USAGE)),
+        }
+    };
+    let (threads, min) = match args.as_slice() {
+        [] => (4, 500),
+        [t] => (parse(t, "thread count"), 500),
+        [t, ms] => (parse(t, "thread count"), parse(ms, "duration")),
+        _ => fail(USAGE),
+    };
+    let min = Duration::from_millis(min);
+
+    println!("capacity {}, keys {}", CAPACITY, KEYS);
+    println!("{:<6} {:>8} {:>14} {:>9}", "policy", "threads", "Mops/s", "hit ratio");
+    let mut counts = vec![1];
+    if threads > 1 {
+        counts.push(threads as usize);
+    }
+    for n in counts {
+        for (name, bench) in [("clock", clock as fn(usize, Duration) -> (f64, f64)), ("lru", lru)] {
+            let (ops, ratio) = bench(n, min);
+            println!("{:<6} {:>8} {:>14.2} {:>9.3}", name, n, ops / 1e6, ratio);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// RFC 4648 Base32，每 5 位一个字符，8 个字符一块对应 5 个字节
+
+use crate::codec::codec::{Codec, DecodingError, digits_map, encode_bits, decode_bits};
+
+// Base32 编码字符
+const ALPHABET:&[u8;32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+// 进制映射关系
+const DIGITS_MAP:[u8;128] = digits_map(ALPHABET);
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct Base32 {
+    padded:bool,
+}
+
+impl Base32 {
+    // 用 = 补齐到 8 的倍数
+    pub const STANDARD:Base32 = Base32{ padded:true };
+
+    // 不补齐
+    pub const NO_PAD:Base32 = Base32{ padded:false };
+}
+
+impl Codec for Base32 {
+    fn encode(&self, bytes:&[u8]) -> String {
+        encode_bits(bytes, 5, ALPHABET, self.padded)
+    }
+
+    fn decode(&self, s:&str) -> Result<Vec<u8>, DecodingError> {
+        decode_bits(s, 5, &DIGITS_MAP, self.padded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::codec::{Encoder, Decoder};
+
+    // RFC 4648 第 10 节的测试向量
+    const VECTORS:&[(&str, &str)] = &[
+        ("", ""),
+        ("f", "MY======"),
+        ("fo", "MZXQ===="),
+        ("foo", "MZXW6==="),
+        ("foob", "MZXW6YQ="),
+        ("fooba", "MZXW6YTB"),
+        ("foobar", "MZXW6YTBOI======"),
+    ];
+
+    #[test]
+    fn it_works() {
+        for &(plain, encoded) in VECTORS {
+            assert_eq!(plain.encode(&Base32::STANDARD), encoded);
+            assert_eq!(encoded.decode(&Base32::STANDARD), Ok(plain.as_bytes().to_vec()));
+
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(plain.encode(&Base32::NO_PAD), unpadded);
+            assert_eq!(unpadded.decode(&Base32::NO_PAD), Ok(plain.as_bytes().to_vec()));
+        }
+
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(Base32::STANDARD.decode(&Base32::STANDARD.encode(&bytes)), Ok(bytes));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let b32 = Base32::STANDARD;
+        assert_eq!(b32.decode("MZXW6YQ"), Err(DecodingError::InvalidPadding));
+        assert_eq!(b32.decode("MZXW6YQ=="), Err(DecodingError::InvalidPadding));
+        assert_eq!(b32.decode("MZ=XW6YQ"), Err(DecodingError::InvalidPadding));
+        assert_eq!(b32.decode("mzxw6yq="), Err(DecodingError::InvalidCharacter('m', 0)));
+        assert_eq!(b32.decode("MZXW1YQ="), Err(DecodingError::InvalidCharacter('1', 4)));
+        assert_eq!(b32.decode("M======="), Err(DecodingError::InvalidLength));
+        // 末尾多余的比特不为 0
+        assert_eq!(b32.decode("MZ======"), Err(DecodingError::Invalid));
+        assert_eq!(Base32::NO_PAD.decode("MY======"), Err(DecodingError::InvalidPadding));
+    }
+}
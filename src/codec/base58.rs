@@ -0,0 +1,476 @@
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use crate::codec::codec::{Codec, DecodingError, digits_map};
+
+// 最大转换进制58
+const BIG_RADIX:u32 = 58;
+
+// 快速实现中每个 limb 表示的 Base58 位数，58^10 < 2^64
+const LIMB_DIGITS:usize = 10;
+
+// 58^0 到 58^10
+const POWERS:[u64;LIMB_DIGITS + 1] = {
+    let mut powers = [1u64;LIMB_DIGITS + 1];
+    let mut i = 1;
+    while i <= LIMB_DIGITS {
+        powers[i] = powers[i - 1] * BIG_RADIX as u64;
+        i += 1;
+    }
+    powers
+};
+
+// 一个 limb 的进制
+const LIMB_RADIX:u64 = POWERS[LIMB_DIGITS];
+
+// Base58Check 校验和的字节数
+const CHECKSUM_LEN:usize = 4;
+
+// Base58 字母表，第 0 个字符表示前置0，解码用的映射表由字母表生成
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct Alphabet {
+    chars:[u8;58],
+    digits:[u8;128],        // 进制映射关系
+}
+
+// 自定义字母表的错误
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum AlphabetError {
+    InvalidLength(usize),   // 不是 58 个字符
+    NonAscii(char),
+    Duplicate(char),
+}
+
+impl Alphabet {
+    // 比特币使用的字母表
+    pub const BITCOIN:Alphabet = Alphabet::build(b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz");
+
+    // Ripple (XRP) 使用的字母表
+    pub const RIPPLE:Alphabet = Alphabet::build(b"rpshnaf39wBUDNEGHJKLM4PQRST7VWXYZ2bcdeCg65jkm8oFqi1tuvAxyz");
+
+    // Flickr 短链接使用的字母表
+    pub const FLICKR:Alphabet = Alphabet::build(b"123456789abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ");
+
+    // 内置字母表已知合法，不做检查
+    const fn build(chars:&[u8;58]) -> Alphabet {
+        Alphabet{ chars:*chars, digits:digits_map(chars) }
+    }
+
+    // 自定义字母表，必须是 58 个互不相同的 ASCII 字符
+    pub fn new(chars:&str) -> Result<Alphabet, AlphabetError> {
+        if let Some(c) = chars.chars().find(|c| !c.is_ascii()) {
+            return Err(AlphabetError::NonAscii(c));
+        }
+        let bytes: &[u8;58] = chars.as_bytes().try_into()
+            .map_err(|_| AlphabetError::InvalidLength(chars.len()))?;
+        for (i, &b) in bytes.iter().enumerate() {
+            if bytes[..i].contains(&b) {
+                return Err(AlphabetError::Duplicate(b as char));
+            }
+        }
+        Ok(Alphabet::build(bytes))
+    }
+
+    // 字母表的字符
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.chars).unwrap()
+    }
+
+    // 字符对应的数值
+    fn digit(&self, c:char) -> Option<u8> {
+        match self.digits.get(c as usize) {
+            Some(&d) if d != 255 => Some(d),
+            _ => None,
+        }
+    }
+
+    // 表示前置0的字符
+    fn zero(&self) -> char {
+        self.chars[0] as char
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::BITCOIN
+    }
+}
+
+// 把任意字节编码为 Base58 字符串。
+// 每次读入 8 个字节，结果按 58^10 进制存放在 u64 中，
+// 内层循环的次数约为逐字节转换的 1/80
+pub fn encode(bytes:&[u8], alphabet:&Alphabet) -> String {
+    // 统计前置0的个数
+    let zero_count = bytes.iter().take_while(|b| **b == 0).count();
+    let bytes = &bytes[zero_count..];
+
+    // 第一块不足 8 个字节，后面都是整块
+    let head = bytes.len() % 8;
+    let chunks = std::iter::once(&bytes[..head]).filter(|c| !c.is_empty())
+        .chain(bytes[head..].chunks(8));
+
+    // 进制转换，limbs 中低位在前，每个 limb 是 58^10 进制的一位
+    let mut limbs: Vec<u64> = Vec::with_capacity(bytes.len() * 138 / 100 / LIMB_DIGITS + 1);
+    for chunk in chunks {
+        let shift = chunk.len() * 8;
+        let mut carry = chunk.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+        for limb in limbs.iter_mut() {
+            // limb < 58^10，所以商小于 2^64
+            let acc = ((*limb as u128) << shift) | carry as u128;
+            let q = acc / LIMB_RADIX as u128;
+            *limb = (acc - q * LIMB_RADIX as u128) as u64;
+            carry = q as u64;
+        }
+        while carry != 0 {
+            limbs.push(carry % LIMB_RADIX);
+            carry /= LIMB_RADIX;
+        }
+    }
+
+    // 处理多个前置0，再从高位到低位获取编码后的字符
+    let mut result_str = String::with_capacity(zero_count + limbs.len() * LIMB_DIGITS);
+    for _ in 0..zero_count {
+        result_str.push(alphabet.zero());
+    }
+    for (i, &limb) in limbs.iter().rev().enumerate() {
+        let mut digits = [0u8;LIMB_DIGITS];
+        let mut n = limb;
+        for d in digits.iter_mut().rev() {
+            *d = (n % BIG_RADIX as u64) as u8;
+            n /= BIG_RADIX as u64;
+        }
+        // 最高的 limb 不输出前面的 0
+        let start = if i == 0 { digits.iter().take_while(|&&d| d == 0).count() } else { 0 };
+        for &d in &digits[start..] {
+            result_str.push(alphabet.chars[d as usize] as char);
+        }
+    }
+
+    // 返回编码后的字符串
+    result_str
+}
+
+// 把 Base58 字符串解码为字节。
+// 每次读入 10 个字符，结果按 2^64 进制存放在 u64 中
+pub fn decode(s:&str, alphabet:&Alphabet) -> Result<Vec<u8>, DecodingError> {
+    // 统计表示前置0的字符个数（比特币字母表中是 1）
+    let zero_count = s.chars().take_while(|&c| c == alphabet.zero()).count();
+
+    let mut digits: Vec<u8> = Vec::with_capacity(s.len());
+    for (i, c) in s.chars().enumerate().skip(zero_count) {
+        // 错误字符
+        match alphabet.digit(c) {
+            Some(d) => digits.push(d),
+            None => return Err(DecodingError::InvalidCharacter(c, i)),
+        }
+    }
+
+    // 第一块不足 10 个字符，后面都是整块
+    let head = digits.len() % LIMB_DIGITS;
+    let chunks = std::iter::once(&digits[..head]).filter(|c| !c.is_empty())
+        .chain(digits[head..].chunks(LIMB_DIGITS));
+
+    // 进制转换，limbs 中低位在前
+    let mut limbs: Vec<u64> = Vec::with_capacity(digits.len() * 733 / 1000 / 8 + 1);
+    for chunk in chunks {
+        let mul = POWERS[chunk.len()] as u128;
+        let mut carry = chunk.iter().fold(0u64, |acc, &d| acc * BIG_RADIX as u64 + d as u64);
+        for limb in limbs.iter_mut() {
+            // 进位始终小于 58^10
+            let acc = *limb as u128 * mul + carry as u128;
+            *limb = acc as u64;
+            carry = (acc >> 64) as u64;
+        }
+        if carry != 0 {
+            limbs.push(carry);
+        }
+    }
+
+    // 补上前置0，再从高位到低位输出，最高的 limb 不输出前面的 0
+    let mut bytes: Vec<u8> = Vec::with_capacity(zero_count + limbs.len() * 8);
+    bytes.resize(zero_count, 0);
+    for (i, &limb) in limbs.iter().rev().enumerate() {
+        let be = limb.to_be_bytes();
+        let start = if i == 0 { be.iter().take_while(|&&b| b == 0).count() } else { 0 };
+        bytes.extend_from_slice(&be[start..]);
+    }
+    Ok(bytes)
+}
+
+// 逐字节做进制转换的编码，复杂度 O(n²) 且每次只处理一个字节。
+// 结果与 encode 完全相同，保留用于对照测试和基准测试
+pub fn encode_bytewise(bytes:&[u8], alphabet:&Alphabet) -> String {
+    // 统计前置0的个数
+    let zero_count = bytes.iter().take_while(|b| **b == 0).count();
+
+    // 转换后所需空间：log(256)/log(58)
+    // 前置0不需要，所以删除
+    let size = (bytes.len() - zero_count) * 138 / 100 + 1;
+
+    // 字符进制转换，digits 中低位在前
+    let mut digits: Vec<u8> = Vec::with_capacity(size);
+    for &b in bytes[zero_count..].iter() {
+        // carry为从前往后读取的字节
+        let mut carry = b as u32;
+        for d in digits.iter_mut() {
+            carry += (*d as u32) << 8;
+            *d = (carry % BIG_RADIX) as u8;
+            carry /= BIG_RADIX;
+        }
+        while carry != 0 {
+            digits.push((carry % BIG_RADIX) as u8);
+            carry /= BIG_RADIX;
+        }
+    }
+
+    // 处理多个前置0，再从高位到低位获取编码后的字符
+    let mut result_str = String::with_capacity(zero_count + digits.len());
+    for _ in 0..zero_count {
+        result_str.push(alphabet.zero());
+    }
+    for &d in digits.iter().rev() {
+        result_str.push(alphabet.chars[d as usize] as char);
+    }
+
+    // 返回编码后的字符串
+    result_str
+}
+
+// 逐字符做进制转换的解码，结果与 decode 完全相同
+pub fn decode_bytewise(s:&str, alphabet:&Alphabet) -> Result<Vec<u8>, DecodingError> {
+    // 统计表示前置0的字符个数（比特币字母表中是 1）
+    let zero_count = s.chars().take_while(|&c| c == alphabet.zero()).count();
+
+    // 进制转换，bytes 中低位在前
+    let mut bytes: Vec<u8> = Vec::with_capacity(s.len() * 733 / 1000 + 1);
+    for (i, c) in s.chars().enumerate().skip(zero_count) {
+        // 错误字符
+        let digit = match alphabet.digit(c) {
+            Some(d) => d,
+            None => return Err(DecodingError::InvalidCharacter(c, i)),
+        };
+
+        let mut carry = digit as u32;
+        for b in bytes.iter_mut() {
+            carry += *b as u32 * BIG_RADIX;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry != 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    // 补上前置0，再转为高位在前
+    bytes.resize(bytes.len() + zero_count, 0);
+    bytes.reverse();
+    Ok(bytes)
+}
+
+// 两次 SHA-256 的前 4 个字节
+fn checksum(bytes:&[u8]) -> [u8;CHECKSUM_LEN] {
+    let mut hasher = Sha256::new();
+    let mut hash = [0u8;32];
+    hasher.input(bytes);
+    hasher.result(&mut hash);
+    hasher.reset();
+    hasher.input(&hash);
+    hasher.result(&mut hash);
+
+    let mut sum = [0u8;CHECKSUM_LEN];
+    sum.copy_from_slice(&hash[..CHECKSUM_LEN]);
+    sum
+}
+
+// Base58Check 编码：版本字节 + 数据 + 校验和
+pub fn encode_check(version:u8, payload:&[u8], alphabet:&Alphabet) -> String {
+    let mut bytes = Vec::with_capacity(1 + payload.len() + CHECKSUM_LEN);
+    bytes.push(version);
+    bytes.extend_from_slice(payload);
+    let sum = checksum(&bytes);
+    bytes.extend_from_slice(&sum);
+    encode(&bytes, alphabet)
+}
+
+// Base58Check 解码，校验通过后返回 (版本字节, 数据)
+pub fn decode_check(s:&str, alphabet:&Alphabet) -> Result<(u8, Vec<u8>), DecodingError> {
+    let mut bytes = decode(s, alphabet)?;
+    if bytes.len() < 1 + CHECKSUM_LEN {
+        return Err(DecodingError::InvalidLength);
+    }
+    let sum = bytes.split_off(bytes.len() - CHECKSUM_LEN);
+    if sum != checksum(&bytes) {
+        return Err(DecodingError::BadChecksum);
+    }
+    Ok((bytes[0], bytes.split_off(1)))
+}
+
+impl Codec for Alphabet {
+    fn encode(&self, bytes:&[u8]) -> String {
+        encode(bytes, self)
+    }
+
+    fn decode(&self, s:&str) -> Result<Vec<u8>, DecodingError> {
+        decode(s, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::codec::{Encoder, Decoder};
+    use crate::serializer::serializer::from_hex;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    const BTC:&Alphabet = &Alphabet::BITCOIN;
+
+    // Bitcoin Core 的测试向量
+    const VECTORS:&[(&str, &str)] = &[
+        ("", ""),
+        ("61", "2g"),
+        ("626262", "a3gV"),
+        ("636363", "aPEr"),
+        ("73696d706c792061206c6f6e6720737472696e67", "2cFupjhnEsSn59qHXstmK2ffpLv2"),
+        ("00eb15231dfceb60925886b67d065299925915aeb172c06647", "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L"),
+        ("516b6fcd0f", "ABnLTmg"),
+        ("bf4f89001e670274dd", "3SEo3LWLoPntC"),
+        ("572e4794", "3EFU7m"),
+        ("ecac89cad93923c02321", "EJDM8drfXA6uyA"),
+        ("10c8511e", "Rt5zm"),
+        ("00000000000000000000", "1111111111"),
+    ];
+
+    #[test]
+    fn it_works() {
+        for &(hex, b58) in VECTORS {
+            let bytes = from_hex(hex).unwrap();
+            assert_eq!(encode(&bytes, BTC), b58);
+            assert_eq!(decode(b58, BTC), Ok(bytes));
+        }
+        assert_eq!("Hello World!".encode(BTC), "2NEpo7TZRRrLZSi2U");
+        assert_eq!("2NEpo7TZRRrLZSi2U".decode(BTC), Ok(b"Hello World!".to_vec()));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // 前置0、全0、全0xff以及较长的数据
+        let mut cases: Vec<Vec<u8>> = vec![
+            vec![0], vec![0, 0, 0], vec![0, 0, 1], vec![0, 0xff, 0], vec![0xff; 64],
+            vec![0, 0, 0x28, 0x7f, 0xb4, 0xcd],
+        ];
+        cases.push((0..=255).collect());
+        cases.push((0..1000u32).map(|i| (i * 7919 % 251) as u8).collect());
+        for bytes in cases {
+            let s = bytes.encode(BTC);
+            assert_eq!(s.chars().take_while(|&c| c == '1').count(),
+                bytes.iter().take_while(|&&b| b == 0).count());
+            assert_eq!(decode(&s, BTC), Ok(bytes));
+        }
+        assert_eq!(encode(&[0, 0, 0x28, 0x7f, 0xb4, 0xcd], BTC), "11233QC4");
+    }
+
+    #[test]
+    fn test_same_as_bytewise() {
+        let mut rng = StdRng::seed_from_u64(58);
+
+        // 随机长度、随机前置0的数据，编码结果和逐字节实现完全相同
+        for _ in 0..500 {
+            let zeros = rng.gen_range(0..4);
+            let len = rng.gen_range(0..300);
+            let mut bytes = vec![0u8; zeros];
+            bytes.extend((0..len).map(|_| rng.gen::<u8>()));
+            let s = encode(&bytes, BTC);
+            assert_eq!(s, encode_bytewise(&bytes, BTC));
+            assert_eq!(decode(&s, BTC), Ok(bytes));
+        }
+
+        // 正好在 limb 边界上的长度，以及较长的数据
+        for len in [7, 8, 9, 15, 16, 17, 64, 4096] {
+            let bytes: Vec<u8> = (0..len).map(|_| rng.gen::<u8>()).collect();
+            assert_eq!(encode(&bytes, BTC), encode_bytewise(&bytes, BTC));
+            let ff = vec![0xff; len];
+            assert_eq!(encode(&ff, &Alphabet::RIPPLE), encode_bytewise(&ff, &Alphabet::RIPPLE));
+        }
+
+        // 随机字符串（不一定是某个字节串的编码），解码结果和错误位置都相同
+        let chars: Vec<char> = BTC.as_str().chars().chain(['0', 'O', 'é']).collect();
+        for _ in 0..500 {
+            let len = rng.gen_range(0..120);
+            let mut s: String = (0..len).map(|_| chars[rng.gen_range(0..58)]).collect();
+            if rng.gen_bool(0.2) {
+                s.push(chars[rng.gen_range(0..chars.len())]);
+            }
+            assert_eq!(decode(&s, BTC), decode_bytewise(&s, BTC), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_invalid_character() {
+        assert_eq!(decode("11O1", BTC), Err(DecodingError::InvalidCharacter('O', 2)));
+        assert_eq!(decode("2g0", BTC), Err(DecodingError::InvalidCharacter('0', 2)));
+        assert_eq!(decode("abcI", BTC), Err(DecodingError::InvalidCharacter('I', 3)));
+        assert_eq!(decode("1l", BTC), Err(DecodingError::InvalidCharacter('l', 1)));
+
+        // 位置按字符计算，多字节字符也只算一个
+        assert_eq!(decode("é2g", BTC), Err(DecodingError::InvalidCharacter('é', 0)));
+        assert_eq!(decode("2gé€", BTC), Err(DecodingError::InvalidCharacter('é', 2)));
+        assert_eq!(decode("2 g", BTC), Err(DecodingError::InvalidCharacter(' ', 1)));
+    }
+
+    #[test]
+    fn test_check() {
+        // 比特币地址：版本 0 + 公钥哈希
+        let hash160 = from_hex("010966776006953d5567439e5e39f86a0d273bee").unwrap();
+        let addr = encode_check(0, &hash160, BTC);
+        assert_eq!(addr, "16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM");
+        assert_eq!(decode_check(&addr, BTC), Ok((0, hash160)));
+
+        // 版本字节和空数据
+        assert_eq!(decode_check(&encode_check(0x80, &[], BTC), BTC), Ok((0x80, vec![])));
+        assert_eq!(decode_check(&encode_check(5, &[0, 0, 1], BTC), BTC), Ok((5, vec![0, 0, 1])));
+
+        // 改动任意一个字符校验都会失败
+        let mut chars: Vec<char> = addr.chars().collect();
+        chars[10] = if chars[10] == 'a' { 'b' } else { 'a' };
+        let tampered: String = chars.into_iter().collect();
+        assert_eq!(decode_check(&tampered, BTC), Err(DecodingError::BadChecksum));
+
+        assert_eq!(decode_check("2g", BTC), Err(DecodingError::InvalidLength));
+        assert_eq!(decode_check("16UwLL9Risc3Qf0", BTC), Err(DecodingError::InvalidCharacter('0', 14)));
+    }
+
+    #[test]
+    fn test_alphabet() {
+        // XRP 的零账户和一号账户
+        assert_eq!(encode_check(0, &[0; 20], &Alphabet::RIPPLE), "rrrrrrrrrrrrrrrrrrrrrhoLvTp");
+        let mut one = [0u8; 20];
+        one[19] = 1;
+        assert_eq!(encode_check(0, &one, &Alphabet::RIPPLE), "rrrrrrrrrrrrrrrrrrrrBZbvji");
+        assert_eq!(decode_check("rrrrrrrrrrrrrrrrrrrrBZbvji", &Alphabet::RIPPLE), Ok((0, one.to_vec())));
+        assert_eq!(decode("r0", &Alphabet::RIPPLE), Err(DecodingError::InvalidCharacter('0', 1)));
+
+        // 不同字母表的编码结果逐字符一一对应
+        let bytes: Vec<u8> = (0..100u32).map(|i| (i * 131 % 256) as u8).collect();
+        let flickr = encode(&bytes, &Alphabet::FLICKR);
+        let translated: String = encode(&bytes, BTC).chars()
+            .map(|c| Alphabet::FLICKR.chars[BTC.digit(c).unwrap() as usize] as char)
+            .collect();
+        assert_eq!(flickr, translated);
+        assert_eq!(decode(&flickr, &Alphabet::FLICKR), Ok(bytes.clone()));
+
+        // 自定义字母表
+        let custom = Alphabet::new("ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz123456789").unwrap();
+        assert_eq!(custom.as_str().len(), 58);
+        let s = encode(&[0, 0, 1, 2, 3], &custom);
+        assert!(s.starts_with("AA"));
+        assert_eq!(decode(&s, &custom), Ok(vec![0, 0, 1, 2, 3]));
+        assert_eq!(Alphabet::default(), Alphabet::BITCOIN);
+        assert_eq!(Alphabet::new(Alphabet::BITCOIN.as_str()), Ok(Alphabet::BITCOIN));
+
+        assert_eq!(Alphabet::new("abc"), Err(AlphabetError::InvalidLength(3)));
+        assert_eq!(Alphabet::new(&"é".repeat(29)), Err(AlphabetError::NonAscii('é')));
+        let dup = format!("{}1", &Alphabet::BITCOIN.as_str()[..57]);
+        assert_eq!(Alphabet::new(&dup), Err(AlphabetError::Duplicate('1')));
+    }
+}
@@ -0,0 +1,95 @@
+// RFC 4648 Base64，每 6 位一个字符，4 个字符一块对应 3 个字节。
+// URL 安全的版本用 - 和 _ 代替 + 和 /
+
+use crate::codec::codec::{Codec, DecodingError, digits_map, encode_bits, decode_bits};
+
+// 标准 Base64 编码字符
+const STANDARD_ALPHABET:&[u8;64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// URL 安全的 Base64 编码字符
+const URL_SAFE_ALPHABET:&[u8;64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// 进制映射关系
+const STANDARD_DIGITS:[u8;128] = digits_map(STANDARD_ALPHABET);
+const URL_SAFE_DIGITS:[u8;128] = digits_map(URL_SAFE_ALPHABET);
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct Base64 {
+    url_safe:bool,
+    padded:bool,
+}
+
+impl Base64 {
+    pub const STANDARD:Base64 = Base64{ url_safe:false, padded:true };
+    pub const STANDARD_NO_PAD:Base64 = Base64{ url_safe:false, padded:false };
+    pub const URL_SAFE:Base64 = Base64{ url_safe:true, padded:true };
+    pub const URL_SAFE_NO_PAD:Base64 = Base64{ url_safe:true, padded:false };
+}
+
+impl Codec for Base64 {
+    fn encode(&self, bytes:&[u8]) -> String {
+        let chars = if self.url_safe { URL_SAFE_ALPHABET } else { STANDARD_ALPHABET };
+        encode_bits(bytes, 6, chars, self.padded)
+    }
+
+    fn decode(&self, s:&str) -> Result<Vec<u8>, DecodingError> {
+        let digits = if self.url_safe { &URL_SAFE_DIGITS } else { &STANDARD_DIGITS };
+        decode_bits(s, 6, digits, self.padded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::codec::{Encoder, Decoder};
+
+    // RFC 4648 第 10 节的测试向量
+    const VECTORS:&[(&str, &str)] = &[
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn it_works() {
+        for &(plain, encoded) in VECTORS {
+            assert_eq!(plain.encode(&Base64::STANDARD), encoded);
+            assert_eq!(encoded.decode(&Base64::STANDARD), Ok(plain.as_bytes().to_vec()));
+
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(plain.encode(&Base64::STANDARD_NO_PAD), unpadded);
+            assert_eq!(unpadded.decode(&Base64::STANDARD_NO_PAD), Ok(plain.as_bytes().to_vec()));
+        }
+
+        // 两种字母表只在 62、63 上不同
+        let bytes = [0xfb, 0xff, 0xbf];
+        assert_eq!(bytes.encode(&Base64::STANDARD), "+/+/");
+        assert_eq!(bytes.encode(&Base64::URL_SAFE), "-_-_");
+        assert_eq!([0xfb, 0xff].encode(&Base64::URL_SAFE), "-_8=");
+        assert_eq!([0xfb, 0xff].encode(&Base64::URL_SAFE_NO_PAD), "-_8");
+        assert_eq!("-_8".decode(&Base64::URL_SAFE_NO_PAD), Ok(vec![0xfb, 0xff]));
+        assert_eq!("-_8=".decode(&Base64::STANDARD), Err(DecodingError::InvalidCharacter('-', 0)));
+
+        let bytes: Vec<u8> = (0..=255).collect();
+        for codec in [Base64::STANDARD, Base64::STANDARD_NO_PAD, Base64::URL_SAFE, Base64::URL_SAFE_NO_PAD] {
+            assert_eq!(codec.decode(&codec.encode(&bytes)), Ok(bytes.clone()));
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let b64 = Base64::STANDARD;
+        assert_eq!(b64.decode("Zg="), Err(DecodingError::InvalidPadding));
+        assert_eq!(b64.decode("Zg"), Err(DecodingError::InvalidPadding));
+        assert_eq!(b64.decode("Zm9v="), Err(DecodingError::InvalidPadding));
+        assert_eq!(b64.decode("Z==="), Err(DecodingError::InvalidLength));
+        assert_eq!(b64.decode("Zh=="), Err(DecodingError::Invalid));
+        assert_eq!(b64.decode("Zm 9v"), Err(DecodingError::InvalidCharacter(' ', 2)));
+        assert_eq!(Base64::STANDARD_NO_PAD.decode("Zg=="), Err(DecodingError::InvalidPadding));
+        assert_eq!(Base64::STANDARD_NO_PAD.decode("Zm9vY"), Err(DecodingError::InvalidLength));
+    }
+}
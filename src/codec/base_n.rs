@@ -0,0 +1,288 @@
+// 任意进制（2 到 256）的编解码
+// 核心是大整数的进制转换：字节串看作 256 进制的大整数，转换为 radix 进制的数字序列。
+// 和 Base58 一样，每个前置的 0 字节编码为一个表示 0 的字符，保证可以还原。
+// 用于 base36、base62 这类短链接 ID
+
+use std::collections::HashMap;
+use crate::codec::codec::{Codec, DecodingError};
+
+// 自定义字母表的错误
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum AlphabetError {
+    InvalidLength(usize),   // 字符数（即进制）不在 2 到 256 之间
+    Duplicate(char),
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct BaseN {
+    chars:Vec<char>,
+    digits:HashMap<char, u8>,   // 进制映射关系
+}
+
+impl BaseN {
+    // 字母表的字符数就是进制，第 0 个字符表示 0
+    pub fn new(alphabet:&str) -> Result<BaseN, AlphabetError> {
+        let chars: Vec<char> = alphabet.chars().collect();
+        if !(2..=256).contains(&chars.len()) {
+            return Err(AlphabetError::InvalidLength(chars.len()));
+        }
+        let mut digits = HashMap::with_capacity(chars.len());
+        for (i, &c) in chars.iter().enumerate() {
+            if digits.insert(c, i as u8).is_some() {
+                return Err(AlphabetError::Duplicate(c));
+            }
+        }
+        Ok(BaseN{ chars, digits })
+    }
+
+    // 0-9a-z
+    pub fn base36() -> BaseN {
+        BaseN::new("0123456789abcdefghijklmnopqrstuvwxyz").unwrap()
+    }
+
+    // 0-9A-Za-z
+    pub fn base62() -> BaseN {
+        BaseN::new("0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz").unwrap()
+    }
+
+    pub fn radix(&self) -> u32 {
+        self.chars.len() as u32
+    }
+
+    // 编码整数，0 编码为一个表示 0 的字符
+    pub fn encode_u64(&self, n:u64) -> String {
+        let digits = to_digits(&n.to_be_bytes(), self.radix());
+        if digits.is_empty() {
+            return self.chars[0].to_string();
+        }
+        digits.iter().map(|&d| self.chars[d as usize]).collect()
+    }
+
+    // 解码整数，允许有前置的 0，超出 u64 的范围时返回 InvalidLength
+    pub fn decode_u64(&self, s:&str) -> Result<u64, DecodingError> {
+        if s.is_empty() {
+            return Err(DecodingError::InvalidLength);
+        }
+        let bytes = from_digits(&self.digits_of(s)?, self.radix()).unwrap();
+        if bytes.len() > 8 {
+            return Err(DecodingError::InvalidLength);
+        }
+        Ok(bytes.iter().fold(0u64, |n, &b| n << 8 | b as u64))
+    }
+
+    // 字符转换为数字
+    fn digits_of(&self, s:&str) -> Result<Vec<u8>, DecodingError> {
+        s.chars().enumerate().map(|(i, c)| {
+            self.digits.get(&c).copied().ok_or(DecodingError::InvalidCharacter(c, i))
+        }).collect()
+    }
+}
+
+impl Codec for BaseN {
+    fn encode(&self, bytes:&[u8]) -> String {
+        let zero_count = bytes.iter().take_while(|b| **b == 0).count();
+        let digits = to_digits(&bytes[zero_count..], self.radix());
+        let mut result_str = String::with_capacity(zero_count + digits.len());
+        for _ in 0..zero_count {
+            result_str.push(self.chars[0]);
+        }
+        result_str.extend(digits.iter().map(|&d| self.chars[d as usize]));
+        result_str
+    }
+
+    fn decode(&self, s:&str) -> Result<Vec<u8>, DecodingError> {
+        let digits = self.digits_of(s)?;
+        let zero_count = digits.iter().take_while(|d| **d == 0).count();
+        let mut bytes = vec![0u8; zero_count];
+        bytes.extend(from_digits(&digits[zero_count..], self.radix()).unwrap());
+        Ok(bytes)
+    }
+}
+
+// 一个 u32 中最多能放下的 radix 进制位数
+fn limb_digits(radix:u32) -> usize {
+    assert!((2..=256).contains(&radix), "radix must be in 2..=256, got {}", radix);
+    let mut k = 1;
+    while (radix as u64).pow(k + 1) <= 1 << 32 {
+        k += 1;
+    }
+    k as usize
+}
+
+// 高位在前的字节串（256 进制的大整数）转换为高位在前的 radix 进制数字。
+// 结果没有前置的 0，所以 0 转换为空序列。radix 不在 2 到 256 之间时 panic
+pub fn to_digits(bytes:&[u8], radix:u32) -> Vec<u8> {
+    let k = limb_digits(radix);
+    let big = (radix as u64).pow(k as u32);
+    let bytes = &bytes[bytes.iter().take_while(|b| **b == 0).count()..];
+
+    // 第一块不足 4 个字节，后面都是整块
+    let head = bytes.len() % 4;
+    let chunks = std::iter::once(&bytes[..head]).filter(|c| !c.is_empty())
+        .chain(bytes[head..].chunks(4));
+
+    // 进制转换，limbs 中低位在前，每个 limb 是 radix^k 进制的一位
+    let mut limbs: Vec<u32> = Vec::new();
+    for chunk in chunks {
+        let shift = chunk.len() * 8;
+        let mut carry = chunk.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+        for limb in limbs.iter_mut() {
+            let acc = (*limb as u64) << shift | carry;
+            *limb = (acc % big) as u32;
+            carry = acc / big;
+        }
+        while carry != 0 {
+            limbs.push((carry % big) as u32);
+            carry /= big;
+        }
+    }
+
+    // 每个 limb 展开为 k 位，最高的 limb 不输出前面的 0
+    let mut digits = Vec::with_capacity(limbs.len() * k);
+    for (i, &limb) in limbs.iter().rev().enumerate() {
+        let start = digits.len();
+        let mut n = limb;
+        for _ in 0..k {
+            digits.push((n % radix) as u8);
+            n /= radix;
+        }
+        digits[start..].reverse();
+        if i == 0 {
+            let zeros = digits.iter().take_while(|d| **d == 0).count();
+            digits.drain(..zeros);
+        }
+    }
+    digits
+}
+
+// to_digits 的逆过程，结果没有前置的 0。
+// 有数字不小于 radix 时返回 None，radix 不在 2 到 256 之间时 panic
+pub fn from_digits(digits:&[u8], radix:u32) -> Option<Vec<u8>> {
+    let k = limb_digits(radix);
+    if digits.iter().any(|&d| d as u32 >= radix) {
+        return None;
+    }
+    let digits = &digits[digits.iter().take_while(|d| **d == 0).count()..];
+
+    // 第一块不足 k 位，后面都是整块
+    let head = digits.len() % k;
+    let chunks = std::iter::once(&digits[..head]).filter(|c| !c.is_empty())
+        .chain(digits[head..].chunks(k));
+
+    // 进制转换，limbs 中低位在前，每个 limb 是 2^32 进制的一位
+    let mut limbs: Vec<u32> = Vec::new();
+    for chunk in chunks {
+        let mul = (radix as u64).pow(chunk.len() as u32);
+        let mut carry = chunk.iter().fold(0u64, |acc, &d| acc * radix as u64 + d as u64);
+        for limb in limbs.iter_mut() {
+            let acc = *limb as u64 * mul + carry;
+            *limb = acc as u32;
+            carry = acc >> 32;
+        }
+        if carry != 0 {
+            limbs.push(carry as u32);
+        }
+    }
+
+    // 从高位到低位输出，最高的 limb 不输出前面的 0
+    let mut bytes = Vec::with_capacity(limbs.len() * 4);
+    for (i, &limb) in limbs.iter().rev().enumerate() {
+        let be = limb.to_be_bytes();
+        let start = if i == 0 { be.iter().take_while(|b| **b == 0).count() } else { 0 };
+        bytes.extend_from_slice(&be[start..]);
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::codec::{Encoder, Decoder};
+    use crate::codec::base58::{self, Alphabet};
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn it_works() {
+        let b62 = BaseN::base62();
+        assert_eq!(b62.radix(), 62);
+        assert_eq!("hello world".encode(&b62), "AAwf93rvy4aWQVw");
+        assert_eq!("AAwf93rvy4aWQVw".decode(&b62), Ok(b"hello world".to_vec()));
+        assert_eq!([0, 0, 1].encode(&b62), "001");
+        assert_eq!("001".decode(&b62), Ok(vec![0, 0, 1]));
+        assert_eq!("".decode(&b62), Ok(vec![]));
+        assert_eq!("ab-c".decode(&b62), Err(DecodingError::InvalidCharacter('-', 2)));
+
+        // 短链接 ID
+        for (n, s) in [(0, "0"), (61, "z"), (62, "10"), (u64::MAX, "LygHa16AHYF")] {
+            assert_eq!(b62.encode_u64(n), s);
+            assert_eq!(b62.decode_u64(s), Ok(n));
+        }
+        let b36 = BaseN::base36();
+        assert_eq!(b36.encode_u64(u64::MAX), "3w5e11264sgsf");
+        assert_eq!(b36.decode_u64("00zz"), Ok(36 * 36 - 1));
+        assert_eq!(b36.decode_u64("3w5e11264sgsg"), Err(DecodingError::InvalidLength));
+        assert_eq!(b36.decode_u64(""), Err(DecodingError::InvalidLength));
+    }
+
+    #[test]
+    fn test_alphabet() {
+        // 和 Base58 的结果相同
+        let b58 = BaseN::new(Alphabet::BITCOIN.as_str()).unwrap();
+        let mut rng = StdRng::seed_from_u64(256);
+        for _ in 0..200 {
+            let zeros = rng.gen_range(0..3);
+            let len = rng.gen_range(0..100);
+            let mut bytes = vec![0u8; zeros];
+            bytes.extend((0..len).map(|_| rng.gen::<u8>()));
+            let s = bytes.encode(&b58);
+            assert_eq!(s, base58::encode(&bytes, &Alphabet::BITCOIN));
+            assert_eq!(s.decode(&b58), Ok(bytes));
+        }
+
+        // 二进制和十六进制
+        let b2 = BaseN::new("01").unwrap();
+        let b16 = BaseN::new("0123456789abcdef").unwrap();
+        for n in [1u64, 2, 255, 256, 0xdead_beef, u64::MAX] {
+            assert_eq!(b2.encode_u64(n), format!("{:b}", n));
+            assert_eq!(b16.encode_u64(n), format!("{:x}", n));
+        }
+
+        // 256 进制、非 ASCII 字符
+        let all: String = (0..256u32).map(|i| char::from_u32(0x100 + i).unwrap()).collect();
+        let b256 = BaseN::new(&all).unwrap();
+        let bytes: Vec<u8> = vec![0, 0, 7, 255, 0, 128];
+        let s = bytes.encode(&b256);
+        assert_eq!(s.chars().map(|c| (c as u32 - 0x100) as u8).collect::<Vec<u8>>(), bytes);
+        assert_eq!(s.decode(&b256), Ok(bytes));
+        let emoji = BaseN::new("😀😁😂").unwrap();
+        assert_eq!(emoji.encode_u64(5), "😁😂");
+        assert_eq!(emoji.decode_u64("😁😀"), Ok(3));
+
+        assert_eq!(BaseN::new("a"), Err(AlphabetError::InvalidLength(1)));
+        assert_eq!(BaseN::new(&format!("{}x", all)), Err(AlphabetError::InvalidLength(257)));
+        assert_eq!(BaseN::new("abca"), Err(AlphabetError::Duplicate('a')));
+    }
+
+    #[test]
+    fn test_digits() {
+        assert_eq!(to_digits(&[], 10), Vec::<u8>::new());
+        assert_eq!(to_digits(&[0, 0], 10), Vec::<u8>::new());
+        assert_eq!(to_digits(&[1, 0], 10), vec![2, 5, 6]);
+        assert_eq!(to_digits(&[0xff, 0xff], 16), vec![15, 15, 15, 15]);
+        assert_eq!(from_digits(&[0, 2, 5, 6], 10), Some(vec![1, 0]));
+        assert_eq!(from_digits(&[1, 10], 10), None);
+        assert_eq!(from_digits(&[], 7), Some(vec![]));
+
+        // 各种进制下往返转换
+        let mut rng = StdRng::seed_from_u64(36);
+        for radix in 2..=256 {
+            let len = rng.gen_range(1..80);
+            let mut bytes: Vec<u8> = (0..len).map(|_| rng.gen::<u8>()).collect();
+            bytes[0] |= 1;
+            let digits = to_digits(&bytes, radix);
+            assert!(digits[0] != 0 && digits.iter().all(|&d| (d as u32) < radix));
+            assert_eq!(from_digits(&digits, radix), Some(bytes), "radix {}", radix);
+        }
+    }
+}
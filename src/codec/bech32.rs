@@ -0,0 +1,276 @@
+// Bech32（BIP-173）和 Bech32m（BIP-350）
+// 格式为 HRP + 分隔符 1 + 数据 + 6 个字符的校验和，每个字符表示 5 位。
+// 两者只有校验和的常数不同，解码时根据校验和判断是哪一种。
+// 字符串总长度不超过 90，大小写不能混用，编码结果统一为小写
+
+use crate::codec::codec::{DecodingError, digits_map, convert_bits};
+
+// Bech32 编码字符
+const CHARSET:&[u8;32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+// 进制映射关系
+const DIGITS_MAP:[u8;128] = digits_map(CHARSET);
+
+// 分隔符，HRP 中也可以出现 1，以最后一个为准
+const SEPARATOR:char = '1';
+
+// 校验和的字符数
+const CHECKSUM_LEN:usize = 6;
+
+// 字符串的最大长度
+const MAX_LEN:usize = 90;
+
+// BCH 码的生成多项式
+const GENERATOR:[u32;5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Variant {
+    Bech32,
+    Bech32m,
+}
+
+impl Variant {
+    // 校验和的常数
+    fn constant(&self) -> u32 {
+        match self {
+            Variant::Bech32 => 1,
+            Variant::Bech32m => 0x2bc830a3,
+        }
+    }
+}
+
+fn polymod(values:impl Iterator<Item = u8>) -> u32 {
+    let mut chk = 1u32;
+    for v in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x1ffffff) << 5) ^ v as u32;
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+// HRP 展开为高 3 位、0、低 5 位，参与校验和计算
+fn hrp_expand(hrp:&str) -> impl Iterator<Item = u8> + '_ {
+    hrp.bytes().map(|b| b >> 5)
+        .chain(std::iter::once(0))
+        .chain(hrp.bytes().map(|b| b & 31))
+}
+
+fn checksum(hrp:&str, data:&[u8], variant:Variant) -> [u8;CHECKSUM_LEN] {
+    let values = hrp_expand(hrp).chain(data.iter().copied()).chain([0u8;CHECKSUM_LEN]);
+    let m = polymod(values) ^ variant.constant();
+    let mut sum = [0u8;CHECKSUM_LEN];
+    for (i, s) in sum.iter_mut().enumerate() {
+        *s = ((m >> (5 * (5 - i))) & 31) as u8;
+    }
+    sum
+}
+
+// HRP 为 1 到 83 个 ASCII 可见字符（33 到 126），大小写不能混用
+fn check_hrp(hrp:&str) -> Result<(), DecodingError> {
+    if hrp.is_empty() || hrp.len() > MAX_LEN - 1 - CHECKSUM_LEN {
+        return Err(DecodingError::InvalidHrp);
+    }
+    if hrp.bytes().any(|b| !(33..=126).contains(&b)) {
+        return Err(DecodingError::InvalidHrp);
+    }
+    if hrp.bytes().any(|b| b.is_ascii_lowercase()) && hrp.bytes().any(|b| b.is_ascii_uppercase()) {
+        return Err(DecodingError::MixedCase);
+    }
+    Ok(())
+}
+
+// 编码 5 位分组的数据，用于隔离见证地址等需要自己组织分组的场合
+pub fn encode_u5(hrp:&str, data:&[u8], variant:Variant) -> Result<String, DecodingError> {
+    check_hrp(hrp)?;
+    if hrp.len() + 1 + data.len() + CHECKSUM_LEN > MAX_LEN {
+        return Err(DecodingError::InvalidLength);
+    }
+    if data.iter().any(|&d| d > 31) {
+        return Err(DecodingError::Invalid);
+    }
+
+    let hrp = hrp.to_ascii_lowercase();
+    let mut out = String::with_capacity(hrp.len() + 1 + data.len() + CHECKSUM_LEN);
+    out.push_str(&hrp);
+    out.push(SEPARATOR);
+    for &d in data.iter().chain(checksum(&hrp, data, variant).iter()) {
+        out.push(CHARSET[d as usize] as char);
+    }
+    Ok(out)
+}
+
+// 解码为 (小写的 HRP, 5 位分组的数据, 变体)
+pub fn decode_u5(s:&str) -> Result<(String, Vec<u8>, Variant), DecodingError> {
+    if s.len() > MAX_LEN {
+        return Err(DecodingError::InvalidLength);
+    }
+    if let Some((i, c)) = s.chars().enumerate().find(|&(_, c)| !(33..=126).contains(&(c as u32))) {
+        return Err(DecodingError::InvalidCharacter(c, i));
+    }
+    if s.bytes().any(|b| b.is_ascii_lowercase()) && s.bytes().any(|b| b.is_ascii_uppercase()) {
+        return Err(DecodingError::MixedCase);
+    }
+
+    // 以上检查保证了 s 只含 ASCII 字符，字节位置就是字符位置
+    let s = s.to_ascii_lowercase();
+    let pos = s.rfind(SEPARATOR).ok_or(DecodingError::InvalidHrp)?;
+    let (hrp, rest) = (&s[..pos], &s[pos + 1..]);
+    check_hrp(hrp)?;
+    if rest.len() < CHECKSUM_LEN {
+        return Err(DecodingError::InvalidLength);
+    }
+
+    let mut data = Vec::with_capacity(rest.len());
+    for (i, c) in rest.chars().enumerate() {
+        match DIGITS_MAP[c as usize] {
+            255 => return Err(DecodingError::InvalidCharacter(c, pos + 1 + i)),
+            d => data.push(d),
+        }
+    }
+
+    let variant = match polymod(hrp_expand(hrp).chain(data.iter().copied())) {
+        c if c == Variant::Bech32.constant() => Variant::Bech32,
+        c if c == Variant::Bech32m.constant() => Variant::Bech32m,
+        _ => return Err(DecodingError::BadChecksum),
+    };
+    data.truncate(data.len() - CHECKSUM_LEN);
+    Ok((hrp.to_string(), data, variant))
+}
+
+// 编码任意字节
+pub fn encode(hrp:&str, bytes:&[u8], variant:Variant) -> Result<String, DecodingError> {
+    encode_u5(hrp, &convert_bits(bytes, 8, 5, true).unwrap(), variant)
+}
+
+// 解码为 (小写的 HRP, 字节, 变体)
+pub fn decode(s:&str) -> Result<(String, Vec<u8>, Variant), DecodingError> {
+    let (hrp, data, variant) = decode_u5(s)?;
+    let bytes = convert_bits(&data, 5, 8, false).ok_or(DecodingError::InvalidPadding)?;
+    Ok((hrp, bytes, variant))
+}
+
+// 固定 HRP 和变体的编解码器，解码时 HRP 和变体都必须一致。
+// 长度有上限，编码可能失败，所以不实现 Codec
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Bech32 {
+    hrp:String,
+    variant:Variant,
+}
+
+impl Bech32 {
+    pub fn new(hrp:&str, variant:Variant) -> Result<Self, DecodingError> {
+        check_hrp(hrp)?;
+        Ok(Bech32{ hrp:hrp.to_ascii_lowercase(), variant })
+    }
+
+    pub fn hrp(&self) -> &str {
+        &self.hrp
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    // 结果超过 90 个字符时返回 InvalidLength
+    pub fn encode(&self, bytes:&[u8]) -> Result<String, DecodingError> {
+        encode(&self.hrp, bytes, self.variant)
+    }
+
+    pub fn decode(&self, s:&str) -> Result<Vec<u8>, DecodingError> {
+        let (hrp, bytes, variant) = decode(s)?;
+        if hrp != self.hrp {
+            return Err(DecodingError::InvalidHrp);
+        }
+        if variant != self.variant {
+            return Err(DecodingError::BadChecksum);
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serializer::serializer::from_hex;
+
+    #[test]
+    fn it_works() {
+        // BIP-173 和 BIP-350 的合法字符串
+        let valid = [
+            ("A12UEL5L", Variant::Bech32),
+            ("a12uel5l", Variant::Bech32),
+            ("an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs", Variant::Bech32),
+            ("abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw", Variant::Bech32),
+            ("11qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqc8247j", Variant::Bech32),
+            ("split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w", Variant::Bech32),
+            ("?1ezyfcl", Variant::Bech32),
+            ("A1LQFN3A", Variant::Bech32m),
+            ("a1lqfn3a", Variant::Bech32m),
+            ("abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx", Variant::Bech32m),
+            ("split1checkupstagehandshakeupstreamerranterredcaperredlc445v", Variant::Bech32m),
+            ("?1v759aa", Variant::Bech32m),
+        ];
+        for (s, variant) in valid {
+            let (hrp, data, v) = decode_u5(s).unwrap();
+            assert_eq!(v, variant, "{}", s);
+            assert_eq!(encode_u5(&hrp, &data, v).unwrap(), s.to_ascii_lowercase());
+        }
+
+        // 隔离见证地址：第一个分组是见证版本，其余是见证程序
+        let (hrp, data, variant) = decode_u5("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4").unwrap();
+        assert_eq!((hrp.as_str(), data[0], variant), ("bc", 0, Variant::Bech32));
+        assert_eq!(convert_bits(&data[1..], 5, 8, false),
+            from_hex("751e76e8199196d454941c45d1b3a323f1433bd6"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let cases = [
+            ("pzry9x0s0muk", DecodingError::InvalidHrp),                // 没有分隔符
+            ("1pzry9x0s0muk", DecodingError::InvalidHrp),               // HRP 为空
+            ("x1b4n0q5v", DecodingError::InvalidCharacter('b', 2)),
+            ("li1dgmt3", DecodingError::InvalidLength),                 // 校验和太短
+            ("A1G7SGD8", DecodingError::BadChecksum),                   // 用大写的 HRP 计算的校验和
+            ("A12uEL5L", DecodingError::MixedCase),
+            ("a12uel5m", DecodingError::BadChecksum),
+            ("\u{7f}1axkwrx", DecodingError::InvalidCharacter('\u{7f}', 0)),
+            ("an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx",
+                DecodingError::InvalidLength),
+        ];
+        for (s, err) in cases {
+            assert_eq!(decode_u5(s), Err(err), "{}", s);
+        }
+        assert_eq!(encode_u5("", &[], Variant::Bech32), Err(DecodingError::InvalidHrp));
+        assert_eq!(encode_u5("Ab", &[], Variant::Bech32), Err(DecodingError::MixedCase));
+        assert_eq!(encode_u5("a", &[32], Variant::Bech32), Err(DecodingError::Invalid));
+    }
+
+    #[test]
+    fn test_codec() {
+        let codec = Bech32::new("test", Variant::Bech32m).unwrap();
+        let bytes: Vec<u8> = (0..40).collect();
+        let s = codec.encode(&bytes).unwrap();
+        assert!(s.starts_with("test1"));
+        assert_eq!(codec.decode(&s), Ok(bytes.clone()));
+        assert_eq!(decode(&s.to_ascii_uppercase()), Ok(("test".to_string(), bytes.clone(), Variant::Bech32m)));
+
+        // HRP 或变体不符
+        let other = Bech32::new("TEST", Variant::Bech32).unwrap();
+        assert_eq!(other.hrp(), "test");
+        assert_eq!(other.decode(&s), Err(DecodingError::BadChecksum));
+        let s = encode("tb", &bytes, Variant::Bech32m).unwrap();
+        assert_eq!(codec.decode(&s), Err(DecodingError::InvalidHrp));
+
+        // 超出长度上限时返回错误
+        assert_eq!(codec.encode(&[0; 64]), Err(DecodingError::InvalidLength));
+
+        // 5 位分组转回字节时多余的比特不为 0
+        let s = encode_u5("a", &[31], Variant::Bech32).unwrap();
+        assert_eq!(decode(&s), Err(DecodingError::InvalidPadding));
+    }
+}
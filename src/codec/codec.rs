@@ -0,0 +1,196 @@
+// 二进制数据与文本之间的编解码
+// Base58、Base32、Base64 和任意进制的 BaseN 都实现 Codec，Encoder/Decoder 让字节和字符串
+// 可以直接调用 encode/decode。Bech32 有长度上限，编码可能失败，只提供自己的 encode/decode。
+// 所有编解码使用同一个错误类型
+
+// 定义解码错误的类型
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum DecodingError {
+    Invalid,                            // 数据不合法，比如末尾多余的比特不为 0
+    InvalidLength,
+    InvalidCharacter(char,usize),       // 错误的字符及其位置（第几个字符，从 0 开始）
+    BadChecksum,                        // 校验和不匹配
+    InvalidPadding,                     // 填充字符 = 的位置或个数不对
+    MixedCase,                          // Bech32 中大小写混用
+    InvalidHrp,                         // Bech32 的 HRP 不合法或与预期不符
+}
+
+// 编解码器
+pub trait Codec {
+    fn encode(&self, bytes:&[u8]) -> String;
+
+    fn decode(&self, s:&str) -> Result<Vec<u8>, DecodingError>;
+}
+
+// 定义编码trait
+pub trait Encoder{
+    fn encode<C: Codec + ?Sized>(&self, codec:&C)->String;
+}
+
+// 定义解码trait
+pub trait Decoder{
+    fn decode<C: Codec + ?Sized>(&self, codec:&C)->Result<Vec<u8>,DecodingError>;
+}
+
+impl Encoder for [u8] {
+    fn encode<C: Codec + ?Sized>(&self, codec:&C) -> String {
+        codec.encode(self)
+    }
+}
+
+impl Encoder for str {
+    fn encode<C: Codec + ?Sized>(&self, codec:&C) -> String {
+        codec.encode(self.as_bytes())
+    }
+}
+
+impl Decoder for str {
+    fn decode<C: Codec + ?Sized>(&self, codec:&C) -> Result<Vec<u8>, DecodingError> {
+        codec.decode(self)
+    }
+}
+
+// 字母表生成的映射表，非字母表中的字符为 255
+pub(crate) const fn digits_map(chars:&[u8]) -> [u8;128] {
+    let mut map = [255u8;128];
+    let mut i = 0;
+    while i < chars.len() {
+        map[chars[i] as usize] = i as u8;
+        i += 1;
+    }
+    map
+}
+
+// 按每个字符 bits 位编码，Base32 为 5 位，Base64 为 6 位。
+// 最后不足 bits 位的部分在低位补 0，padded 时用 = 补齐到整块
+pub(crate) fn encode_bits(bytes:&[u8], bits:u32, chars:&[u8], padded:bool) -> String {
+    let block = block_len(bits);
+    let mask = (1u32 << bits) - 1;
+    let mut out = String::with_capacity((bytes.len() * 8).div_ceil(bits as usize) + block);
+    let mut acc = 0u32;
+    let mut nbits = 0;
+    for &b in bytes {
+        acc = (acc << 8) | b as u32;
+        nbits += 8;
+        while nbits >= bits {
+            nbits -= bits;
+            out.push(chars[((acc >> nbits) & mask) as usize] as char);
+        }
+        acc &= (1 << nbits) - 1;
+    }
+    if nbits > 0 {
+        out.push(chars[((acc << (bits - nbits)) & mask) as usize] as char);
+    }
+    if padded {
+        while out.len() % block != 0 {
+            out.push('=');
+        }
+    }
+    out
+}
+
+// encode_bits 的逆过程，padded 时要求补齐到整块，否则不允许出现 =
+pub(crate) fn decode_bits(s:&str, bits:u32, digits:&[u8;128], padded:bool)
+    -> Result<Vec<u8>, DecodingError> {
+    let block = block_len(bits);
+    let mut out = Vec::with_capacity(s.len() * bits as usize / 8);
+    let mut acc = 0u32;
+    let mut nbits = 0;
+    let mut data_len = 0;
+    let mut pad_len = 0;
+    for (i, c) in s.chars().enumerate() {
+        if c == '=' {
+            if !padded {
+                return Err(DecodingError::InvalidPadding);
+            }
+            pad_len += 1;
+            continue;
+        }
+        let digit = match digits.get(c as usize) {
+            Some(&d) if d != 255 => d,
+            _ => return Err(DecodingError::InvalidCharacter(c, i)),
+        };
+        // 填充之后不能再有数据
+        if pad_len > 0 {
+            return Err(DecodingError::InvalidPadding);
+        }
+        data_len += 1;
+        acc = (acc << bits) | digit as u32;
+        nbits += bits;
+        if nbits >= 8 {
+            nbits -= 8;
+            out.push((acc >> nbits) as u8);
+            acc &= (1 << nbits) - 1;
+        }
+    }
+
+    // 最后一块的字符数必须能表示整数个字节
+    let rem = data_len % block;
+    if (rem as u32 * bits) % 8 >= bits {
+        return Err(DecodingError::InvalidLength);
+    }
+    if padded && pad_len != (block - rem) % block {
+        return Err(DecodingError::InvalidPadding);
+    }
+    if acc != 0 {
+        return Err(DecodingError::Invalid);
+    }
+    Ok(out)
+}
+
+// 一块的字符数，正好对应整数个字节
+fn block_len(bits:u32) -> usize {
+    let mut n = 1;
+    while (n * bits) % 8 != 0 {
+        n += 1;
+    }
+    n as usize
+}
+
+// 在不同位宽之间转换，比如 8 位字节和 Bech32 的 5 位分组。
+// pad 为 true 时最后不足 to 位的部分补 0；否则剩余的比特必须不足 from 位且全为 0
+pub(crate) fn convert_bits(data:&[u8], from:u32, to:u32, pad:bool) -> Option<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut nbits = 0;
+    let mask = (1u32 << to) - 1;
+    let mut out = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for &v in data {
+        if (v as u32) >> from != 0 {
+            return None;
+        }
+        acc = (acc << from) | v as u32;
+        nbits += from;
+        while nbits >= to {
+            nbits -= to;
+            out.push(((acc >> nbits) & mask) as u8);
+        }
+        acc &= (1 << nbits) - 1;
+    }
+    if pad {
+        if nbits > 0 {
+            out.push(((acc << (to - nbits)) & mask) as u8);
+        }
+    } else if nbits >= from || acc != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_bits() {
+        let data = vec![0xff, 0x00, 0xab];
+        let five = convert_bits(&data, 8, 5, true).unwrap();
+        assert_eq!(five, vec![31, 28, 0, 10, 22]);
+        assert_eq!(convert_bits(&five, 5, 8, false), Some(data));
+
+        // 多余的比特不为 0，或超出位宽
+        assert_eq!(convert_bits(&[31, 29], 5, 8, false), None);
+        assert_eq!(convert_bits(&[32], 5, 8, true), None);
+        assert_eq!(block_len(5), 8);
+        assert_eq!(block_len(6), 4);
+    }
+}
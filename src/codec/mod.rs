@@ -0,0 +1,6 @@
+pub mod codec;
+pub mod base32;
+pub mod base64;
+pub mod bech32;
+pub mod base58;
+pub mod base_n;
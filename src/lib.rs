@@ -2,4 +2,5 @@ pub mod structure;
 pub mod tim_sort;
 pub mod graph;
 pub mod LRU;
-pub mod serializer;
\ No newline at end of file
+pub mod serializer;
+pub mod codec;
@@ -5,52 +5,102 @@
 use std::thread;
 use std::time::Duration;
 use chrono::prelude::*;
-use crate::serializer::{serialize,hash_str};
-use serde::Serialize;
+use crate::serializer::serializer::{serialize,hash_str};
+use crate::serializer::smt::StateChanges;
+use serde::{Serialize,Deserialize};
+
+// 模拟挖矿的耗时，测试时不等待
+#[cfg(not(test))]
+const MINING_SECS: u64 = 3;
+#[cfg(test)]
+const MINING_SECS: u64 = 0;
 
 // 区块头结构体
-#[derive(Serialize,Debug,PartialEq,Eq)]
+#[derive(Serialize,Deserialize,Debug,Clone,PartialEq,Eq)]
 pub struct BlockHeader {
     pub time:i64,
     pub pre_hash:String,
     pub txs_hash:String,
+    pub state_root:String,      // 账户状态树的根，轻客户端据此校验余额证明
 }
 
 // 区块结构体
-#[derive(Debug)]
+// 区块体被裁剪后 tranxs 为 None，只保留区块头和哈希。
+// state_changes 是相对于前一个区块的状态变化，应用后的状态根必须等于 state_root，
+// 裁剪时保留，用于重放状态
+#[derive(Serialize,Deserialize,Debug,Clone)]
 pub struct Block {
     pub header: BlockHeader,
-    pub tranxs:String,
+    pub tranxs:Option<String>,
+    pub state_changes:StateChanges,
     pub hash:String,
 }
 
 impl Block {
-    pub fn new(txs:String,pre_hash:String)->Self{
+    pub fn new(txs:String,pre_hash:String,state_root:String,state_changes:StateChanges)->Self{
         // 延迟三秒再挖
         println!("Start mining...");
-        thread::sleep(Duration::from_secs(3));
+        thread::sleep(Duration::from_secs(MINING_SECS));
         
         // 准备时间，计算交易哈希值
         let time = Utc::now().timestamp();
-        let txs_hash = serialize(&txs);
-        let txs_hash = hash_str(&txs_hash);
+        let txs_hash = Self::txs_hash(&txs);
         let mut block = Block{
             header: BlockHeader{
                 time:time,
                 txs_hash:txs_hash,
                 pre_hash:pre_hash,
+                state_root:state_root,
             },
-            tranxs:txs,
+            tranxs:Some(txs),
+            state_changes:state_changes,
             hash:"".to_string(),
         }; 
         block.set_hash();
         println!("produce a new block!\n");
         return block;
     }
+
+    // 只有区块头的区块，用于从快照恢复
+    pub fn from_header(header:BlockHeader)->Self{
+        let hash = Self::header_hash(&header);
+        Block{ header, tranxs:None, state_changes:Vec::new(), hash }
+    }
     
     // 计算并设置区块哈希值
     fn set_hash(&mut self){
-        let header_str = serialize(&self.header);
-        self.hash = hash_str(&header_str);
+        self.hash = Self::header_hash(&self.header);
+    }
+
+    // 计算区块头哈希值
+    pub fn header_hash(header:&BlockHeader)->String{
+        let header_str = serialize(header);
+        hash_str(&header_str)
+    }
+
+    // 计算交易哈希值
+    pub fn txs_hash(txs:&str)->String{
+        let txs_ser = serialize(txs);
+        hash_str(&txs_ser)
     }
-}
\ No newline at end of file
+
+    // 裁剪区块体，返回是否真的裁剪了
+    pub fn prune(&mut self)->bool{
+        self.tranxs.take().is_some()
+    }
+
+    pub fn is_pruned(&self)->bool{
+        self.tranxs.is_none()
+    }
+
+    // 校验区块哈希，区块体还在时同时校验交易哈希
+    pub fn is_valid(&self)->bool{
+        if self.hash != Self::header_hash(&self.header){
+            return false;
+        }
+        match &self.tranxs {
+            Some(txs) => Self::txs_hash(txs) == self.header.txs_hash,
+            None => true,
+        }
+    }
+}
@@ -1,44 +1,486 @@
 //有了区块后，接下来就是构建区块链，用Vec存储多个区块
 
 // use crate::block::Block;
-use crate::serializer::block::Block;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::mpsc::{channel,Receiver,Sender};
+use crate::serializer::block::{Block,BlockHeader};
+use crate::serializer::serializer::{serialize,hash_str,to_hex};
+use crate::serializer::smt::{SparseMerkleTree,StateChanges,EMPTY_ROOT};
+use serde::{Serialize,Deserialize};
 
 // 第一个区块没有prehash，所以需要手动设置
 const PRE_HASH: &str = "UnVzdCBsZWFybmluZyBpbiBCbG9jaw==";
 
+// 区块链错误类型
+#[derive(Debug,PartialEq)]
+pub enum ChainError {
+    InvalidBlock(usize),        // 区块哈希或交易哈希不匹配
+    BrokenLink(usize),          // pre_hash 与前一个区块不匹配
+    BadSnapshot,                // 快照哈希不匹配，或与可信的检查点不符
+    BadStateRoot(usize),        // 应用状态变化后的状态根与区块头不匹配
+    MissingState(usize),        // 快照之前的状态未知，不能重组到该高度
+    StaleFork,                  // 分叉不比当前链长，不需要重组
+    Decode,                     // 快照或链文件反序列化失败
+    Io(io::ErrorKind),          // 读写链文件失败
+}
+
+impl From<io::Error> for ChainError {
+    fn from(e:io::Error) -> Self {
+        ChainError::Io(e.kind())
+    }
+}
+
+// 账本状态快照：到某个高度为止的全部区块头，以及该高度的全部账户状态（状态树的叶子），
+// 并用哈希做承诺。状态的根必须等于最后一个区块头中的 state_root
+#[derive(Serialize,Deserialize,Debug,Clone,PartialEq)]
+pub struct Snapshot {
+    pub height:usize,
+    pub headers:Vec<BlockHeader>,
+    pub state:Vec<([u8;32], Vec<u8>)>,
+    pub hash:String,
+}
+
+impl Snapshot {
+    fn commit(height:usize, headers:&[BlockHeader], state:&[([u8;32], Vec<u8>)]) -> String {
+        hash_str(&serialize(&(height, headers, state)))
+    }
+
+    // 校验承诺哈希。哈希只说明快照内容完整，是否可信要由检查点判断
+    pub fn verify(&self) -> bool {
+        self.height + 1 == self.headers.len()
+            && self.hash == Self::commit(self.height, &self.headers, &self.state)
+    }
+
+    // 快照最后一个区块的哈希，用来和可信的检查点比较
+    pub fn tip_hash(&self) -> String {
+        Block::header_hash(&self.headers[self.height])
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serialize(self)
+    }
+
+    pub fn from_bytes(bytes:&[u8]) -> Result<Self, ChainError> {
+        bincode::deserialize(bytes).map_err(|_| ChainError::Decode)
+    }
+}
+
+// 区块链事件，按发生顺序发送给订阅者
+#[derive(Debug,Clone)]
+pub enum ChainEvent {
+    BlockConnected(usize, Block),       // 区块加入链尾
+    BlockDisconnected(usize, Block),    // 重组时区块从链尾移除
+    TxAdded(String),                    // 交易进入交易池
+    TxRemoved(String),                  // 交易离开交易池（被打包）
+}
+
+// 区块的只读视图，带上高度，供输出使用
+#[derive(Serialize,Debug,PartialEq)]
+pub struct BlockView<'a> {
+    pub height: usize,
+    pub hash: &'a str,
+    pub time: i64,
+    pub pre_hash: &'a str,
+    pub txs_hash: &'a str,
+    pub state_root: &'a str,
+    pub tranxs: Option<&'a str>,        // 区块体已被裁剪时为 None
+}
+
+impl<'a> BlockView<'a> {
+    pub fn new(height:usize, b:&'a Block) -> Self {
+        BlockView{
+            height,
+            hash: &b.hash,
+            time: b.header.time,
+            pre_hash: &b.header.pre_hash,
+            txs_hash: &b.header.txs_hash,
+            state_root: &b.header.state_root,
+            tranxs: b.tranxs.as_deref(),
+        }
+    }
+}
+
+// 交易的只读视图，带上所在区块的高度和哈希
+#[derive(Serialize,Debug,PartialEq)]
+pub struct TxView<'a> {
+    pub height: usize,
+    pub block: &'a str,
+    pub txs_hash: &'a str,
+    pub from: &'a str,
+    pub to: &'a str,
+    pub amount: &'a str,
+}
+
+impl<'a> TxView<'a> {
+    // 区块体已被裁剪或交易格式不对时返回 None
+    pub fn new(height:usize, b:&'a Block) -> Option<Self> {
+        let (from, to, amount) = parse_tx(b.tranxs.as_deref()?)?;
+        Some(TxView{ height, block: &b.hash, txs_hash: &b.header.txs_hash, from, to, amount })
+    }
+}
+
 pub struct Blockchain {
     pub blocks: Vec<Block>,
+    // 等待打包的交易
+    pub mempool: Vec<String>,
+    // 工作中的账户状态，新区块提交它相对于链尾状态的变化并记录状态根。
+    // 接收其他节点的区块或重组后重置为链尾状态，未提交的修改被丢弃
+    pub state: SparseMerkleTree,
+    // 链尾区块对应的状态
+    committed: SparseMerkleTree,
+    // 每个区块的撤销记录，移除区块时用来回滚状态
+    undo: Vec<StateChanges>,
+    // 状态已知的最低高度，从快照启动时为快照高度
+    base: usize,
+    // 只保留最近 N 个区块的区块体，None 表示不裁剪
+    prune_depth: Option<usize>,
+    subscribers: Vec<Sender<ChainEvent>>,
 }
 
 impl Blockchain{
     pub fn new() -> Self {
-        Blockchain{ blocks:vec![Self::genesis_block()]}
+        Self::restore(0, SparseMerkleTree::new(), vec![Self::genesis_block()]).unwrap()
+    }
+
+    // 由高度 base 的状态和全部区块重建区块链，base 之后的区块按状态变化重放
+    fn restore(base:usize, state:SparseMerkleTree, blocks:Vec<Block>) -> Result<Self, ChainError> {
+        if base >= blocks.len() {
+            return Err(ChainError::Decode);
+        }
+        if state.root_hex() != blocks[base].header.state_root {
+            return Err(ChainError::BadStateRoot(base));
+        }
+        let mut bc = Blockchain{
+            blocks: Vec::with_capacity(blocks.len()),
+            mempool: Vec::new(),
+            state: SparseMerkleTree::new(),
+            committed: state,
+            undo: Vec::with_capacity(blocks.len()),
+            base,
+            prune_depth: None,
+            subscribers: Vec::new(),
+        };
+        for (height, block) in blocks.into_iter().enumerate() {
+            let undo = if height > base { bc.apply_state(height, &block)? } else { Vec::new() };
+            bc.blocks.push(block);
+            bc.undo.push(undo);
+        }
+        bc.state = bc.committed.clone();
+        Ok(bc)
+    }
+
+    // 开启裁剪的区块链，添加区块时自动裁剪 depth 之前的区块体
+    pub fn with_prune_depth(depth:usize) -> Self {
+        let mut bc = Self::new();
+        bc.prune_depth = Some(depth);
+        bc
     }
     
     // 生成创世区块
     fn genesis_block() -> Block {
-        Block::("创世区块".to_string(),PRE_HASH.to_string())
+        Block::new("创世区块".to_string(),PRE_HASH.to_string(),to_hex(&EMPTY_ROOT),Vec::new())
+    }
+
+    // 链高度，创世区块高度为 0
+    pub fn height(&self) -> usize {
+        self.blocks.len() - 1
+    }
+
+    pub fn tip(&self) -> &Block {
+        &self.blocks[self.blocks.len()-1]
+    }
+    
+    // 订阅区块链事件，接收端被丢弃后自动取消订阅
+    pub fn subscribe(&mut self) -> Receiver<ChainEvent> {
+        let (tx, rx) = channel();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    fn notify(&mut self, event:ChainEvent) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+        self.subscribers.retain(|s| s.send(event.clone()).is_ok());
     }
     
     // 添加区块，形成区块链
     pub fn add_block(&mut self,data:String){
         // 获取前一个区块的hash值
-        let pre_block = &self.blocks[self.blocks.len()-1];
-        let pre_hash = pre_block.hash().clone();
+        let pre_hash = self.tip().hash.clone();
         
-        // 构建新区块并加入区块链
-        let new_block = Block::new(data,pre_hash);
-        self.blocks.push(new_block);
+        // 构建新区块并加入区块链，区块中记录相对于链尾的状态变化
+        let changes = self.committed.diff(&self.state);
+        let new_block = Block::new(data,pre_hash,self.state.root_hex(),changes);
+        let undo = self.committed.apply(&new_block.state_changes);
+        self.connect(new_block, undo);
     }
-    
-    // 输出区块信息
-    pub fn block_info(&self){
-        for b in self.blocks.iter(){
-            println!("{:#?}",b);
+
+    // 把区块的状态变化应用到链尾状态，状态根不匹配时回滚并返回错误
+    fn apply_state(&mut self, height:usize, block:&Block) -> Result<StateChanges, ChainError> {
+        let undo = self.committed.apply(&block.state_changes);
+        if self.committed.root_hex() != block.header.state_root {
+            self.committed.apply(&undo);
+            return Err(ChainError::BadStateRoot(height));
+        }
+        Ok(undo)
+    }
+
+    // 区块加入链尾，并把其中的交易移出交易池，状态变化已经应用，undo 用于回滚
+    fn connect(&mut self, block:Block, undo:StateChanges) {
+        if let Some(txs) = &block.tranxs {
+            if let Some(i) = self.mempool.iter().position(|t| t == txs) {
+                let tx = self.mempool.remove(i);
+                self.notify(ChainEvent::TxRemoved(tx));
+            }
+        }
+        self.blocks.push(block.clone());
+        self.undo.push(undo);
+        self.notify(ChainEvent::BlockConnected(self.height(), block));
+        self.auto_prune();
+    }
+
+    // 移除链尾区块并回滚状态，其中的交易回到交易池
+    fn disconnect(&mut self) -> Block {
+        let height = self.height();
+        let block = self.blocks.pop().unwrap();
+        let undo = self.undo.pop().unwrap();
+        self.committed.apply(&undo);
+        self.notify(ChainEvent::BlockDisconnected(height, block.clone()));
+        if let Some(txs) = &block.tranxs {
+            self.mempool.push(txs.clone());
+            self.notify(ChainEvent::TxAdded(txs.clone()));
+        }
+        block
+    }
+
+    // 提交交易到交易池
+    pub fn submit_tx(&mut self, tx:String) {
+        self.mempool.push(tx.clone());
+        self.notify(ChainEvent::TxAdded(tx));
+    }
+
+    // 打包交易池中最早的交易，交易池为空时返回 false
+    pub fn mine_pending(&mut self) -> bool {
+        if self.mempool.is_empty() {
+            return false;
+        }
+        let tx = self.mempool[0].clone();
+        self.add_block(tx);
+        true
+    }
+
+    // 链重组：丢弃 fork_height 之后的区块，换成更长的分叉
+    pub fn reorg(&mut self, fork_height:usize, branch:Vec<Block>) -> Result<(), ChainError> {
+        if fork_height > self.height() || fork_height + branch.len() <= self.height() {
+            return Err(ChainError::StaleFork);
+        }
+        if fork_height < self.base {
+            return Err(ChainError::MissingState(fork_height));
+        }
+
+        // 先校验整个分叉，失败时链保持不变
+        let mut pre_hash = &self.blocks[fork_height].hash;
+        for (i, b) in branch.iter().enumerate() {
+            let height = fork_height + 1 + i;
+            if !b.is_valid() || b.is_pruned() {
+                return Err(ChainError::InvalidBlock(height));
+            }
+            if &b.header.pre_hash != pre_hash {
+                return Err(ChainError::BrokenLink(height));
+            }
+            pre_hash = &b.hash;
+        }
+
+        // 在副本上回滚到分叉点，再依次应用分叉中的状态变化
+        let mut state = self.committed.clone();
+        for undo in self.undo[fork_height + 1..].iter().rev() {
+            state.apply(undo);
+        }
+        for (i, b) in branch.iter().enumerate() {
+            state.apply(&b.state_changes);
+            if state.root_hex() != b.header.state_root {
+                return Err(ChainError::BadStateRoot(fork_height + 1 + i));
+            }
+        }
+
+        while self.height() > fork_height {
+            self.disconnect();
+        }
+        for b in branch {
+            let undo = self.committed.apply(&b.state_changes);
+            self.connect(b, undo);
+        }
+        self.state = self.committed.clone();
+        Ok(())
+    }
+
+    // 接收其他节点的区块，校验通过后加入区块链
+    pub fn apply_block(&mut self,block:Block) -> Result<(), ChainError> {
+        let height = self.blocks.len();
+        if !block.is_valid() || block.is_pruned() {
+            return Err(ChainError::InvalidBlock(height));
+        }
+        if block.header.pre_hash != self.tip().hash {
+            return Err(ChainError::BrokenLink(height));
+        }
+        let undo = self.apply_state(height, &block)?;
+        self.connect(block, undo);
+        self.state = self.committed.clone();
+        Ok(())
+    }
+
+    fn auto_prune(&mut self) {
+        if let Some(depth) = self.prune_depth {
+            self.prune(depth);
+        }
+    }
+
+    // 裁剪最近 keep 个区块之前的区块体，返回本次裁剪的数量
+    pub fn prune(&mut self, keep:usize) -> usize {
+        let end = self.blocks.len().saturating_sub(keep);
+        let mut count = 0;
+        for b in self.blocks[..end].iter_mut() {
+            if b.prune() {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    // 校验整条链：区块哈希、交易哈希、前后链接，
+    // 以及从 base 开始重放状态变化得到的状态根
+    pub fn verify(&self) -> Result<(), ChainError> {
+        let mut pre_hash = PRE_HASH;
+        for (i, b) in self.blocks.iter().enumerate() {
+            if !b.is_valid() {
+                return Err(ChainError::InvalidBlock(i));
+            }
+            if b.header.pre_hash != pre_hash {
+                return Err(ChainError::BrokenLink(i));
+            }
+            pre_hash = &b.hash;
+        }
+
+        let mut state = self.base_state();
+        if state.root_hex() != self.blocks[self.base].header.state_root {
+            return Err(ChainError::BadStateRoot(self.base));
+        }
+        for (i, b) in self.blocks.iter().enumerate().skip(self.base + 1) {
+            state.apply(&b.state_changes);
+            if state.root_hex() != b.header.state_root {
+                return Err(ChainError::BadStateRoot(i));
+            }
+        }
+        Ok(())
+    }
+
+    // 从链尾状态回滚得到高度 base 的状态
+    fn base_state(&self) -> SparseMerkleTree {
+        let mut state = self.committed.clone();
+        for undo in self.undo.iter().skip(self.base + 1).rev() {
+            state.apply(undo);
         }
+        state
+    }
+
+    // 导出当前高度的快照
+    pub fn snapshot(&self) -> Snapshot {
+        let headers: Vec<BlockHeader> = self.blocks.iter()
+            .map(|b| b.header.clone())
+            .collect();
+        let state: Vec<([u8;32], Vec<u8>)> = self.committed.leaves()
+            .map(|(path, value)| (*path, value.clone()))
+            .collect();
+        let height = self.height();
+        let hash = Snapshot::commit(height, &headers, &state);
+        Snapshot{ height, headers, state, hash }
+    }
+
+    // 从快照启动新节点，之后只需校验快照高度之后的区块。
+    // checkpoint 是从可信渠道得到的快照高度处的区块哈希，快照的链尾必须与之相同，
+    // 账户状态再由区块头中的状态根校验
+    pub fn from_snapshot(snapshot:Snapshot, checkpoint:&str) -> Result<Self, ChainError> {
+        if !snapshot.verify() || snapshot.tip_hash() != checkpoint {
+            return Err(ChainError::BadSnapshot);
+        }
+        let bc = Self::restore(snapshot.height, SparseMerkleTree::from_leaves(snapshot.state),
+            snapshot.headers.into_iter().map(Block::from_header).collect())?;
+        bc.verify()?;
+        Ok(bc)
+    }
+    
+    // 按高度查找区块
+    pub fn block_at(&self, height:usize) -> Option<&Block> {
+        self.blocks.get(height)
+    }
+
+    // 按哈希查找区块，返回高度和区块
+    pub fn find_block(&self, hash:&str) -> Option<(usize, &Block)> {
+        self.blocks.iter().enumerate().find(|(_, b)| b.hash == hash)
+    }
+
+    // 按交易哈希查找交易所在的区块
+    pub fn find_tx(&self, txs_hash:&str) -> Option<(usize, &Block)> {
+        self.blocks.iter().enumerate().find(|(_, b)| b.header.txs_hash == txs_hash)
+    }
+
+    // 账户历史：所有转出或转入该地址且区块体仍在的交易
+    pub fn account_history(&self, addr:&str) -> Vec<(usize, &Block)> {
+        self.blocks.iter().enumerate()
+            .filter(|(_, b)| match b.tranxs.as_deref().and_then(parse_tx) {
+                Some((from, to, _)) => from == addr || to == addr,
+                None => false,
+            })
+            .collect()
+    }
+
+    // 将区块链保存到文件：base、裁剪深度、高度 base 的状态以及全部区块
+    pub fn save<P: AsRef<Path>>(&self, path:P) -> Result<(), ChainError> {
+        let state = self.base_state();
+        let leaves: Vec<(&[u8;32], &Vec<u8>)> = state.leaves().collect();
+        fs::write(path, serialize(&(self.base, self.prune_depth, leaves, &self.blocks)))?;
+        Ok(())
+    }
+
+    // 从文件加载区块链，重放状态变化得到链尾状态
+    pub fn load<P: AsRef<Path>>(path:P) -> Result<Self, ChainError> {
+        let bytes = fs::read(path)?;
+        let (base, prune_depth, leaves, blocks): (usize, Option<usize>, Vec<([u8;32], Vec<u8>)>, Vec<Block>) =
+            bincode::deserialize(&bytes).map_err(|_| ChainError::Decode)?;
+        let mut bc = Self::restore(base, SparseMerkleTree::from_leaves(leaves), blocks)?;
+        bc.prune_depth = prune_depth;
+        Ok(bc)
+    }
+
+    // 裁剪深度，None 表示不裁剪
+    pub fn prune_depth(&self) -> Option<usize> {
+        self.prune_depth
+    }
+
+    // 全部区块的信息，从创世区块开始
+    pub fn block_info(&self) -> Vec<BlockView<'_>> {
+        self.blocks.iter().enumerate()
+            .map(|(h, b)| BlockView::new(h, b))
+            .collect()
+    }
+
+    // 按交易哈希查找交易，区块体已被裁剪时返回 None
+    pub fn tx_info(&self, txs_hash:&str) -> Option<TxView<'_>> {
+        let (height, b) = self.find_tx(txs_hash)?;
+        TxView::new(height, b)
     }
 }
 
+// 解析形如 "0xabcd->0xabce:5 btc" 的交易，返回 (转出, 转入, 金额)
+pub fn parse_tx(tx:&str) -> Option<(&str, &str, &str)> {
+    let (from, rest) = tx.split_once("->")?;
+    let (to, amount) = rest.split_once(':')?;
+    Some((from.trim(), to.trim(), amount.trim()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,7 +493,243 @@ mod tests {
         bc.add_block(tx);
         let tx = "0xabce->0xabcf:10 btc".to_string();
         bc.add_block(String::from(tx));
-        println!("----------------------Block info---------------------------------");
-        bc.block_info();
+        let info = bc.block_info();
+        assert_eq!(info.len(), 3);
+        assert_eq!(info[2], BlockView::new(2, bc.tip()));
+        assert_eq!(info[1].tranxs, Some("0xabcd->0xabce:5 btc"));
+
+        let tx = bc.tx_info(&bc.blocks[2].header.txs_hash).unwrap();
+        assert_eq!((tx.height, tx.block), (2, bc.blocks[2].hash.as_str()));
+        assert_eq!((tx.from, tx.to, tx.amount), ("0xabce", "0xabcf", "10 btc"));
+        assert_eq!(bc.tx_info("missing"), None);
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut bc = Blockchain::with_prune_depth(2);
+        for i in 0..4 {
+            bc.add_block(format!("0xabcd->0xabce:{} btc", i));
+        }
+
+        assert_eq!(bc.height(), 4);
+        assert!(bc.blocks[..3].iter().all(|b| b.is_pruned()));
+        assert!(bc.blocks[3..].iter().all(|b| !b.is_pruned()));
+        assert_eq!(bc.verify(), Ok(()));
+
+        // 裁剪深度随链文件保存，加载后继续自动裁剪
+        let path = std::env::temp_dir().join("rust_studying_test_prune.bin");
+        bc.save(&path).unwrap();
+        let mut loaded = Blockchain::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.prune_depth(), Some(2));
+        loaded.add_block("0xabce->0xabcf:1 btc".to_string());
+        assert!(loaded.blocks[..4].iter().all(|b| b.is_pruned()));
+        assert!(loaded.blocks[4..].iter().all(|b| !b.is_pruned()));
+        assert_eq!(Blockchain::new().prune_depth(), None);
+
+        assert_eq!(bc.prune(1), 1);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut bc = Blockchain::new();
+        bc.state.insert(b"0xabcd", b"5".to_vec());
+        bc.add_block("0xabcd->0xabce:5 btc".to_string());
+        bc.state.insert(b"0xabce", b"10".to_vec());
+        bc.add_block("0xabce->0xabcf:10 btc".to_string());
+
+        let checkpoint = bc.tip().hash.clone();
+        let bytes = bc.snapshot().to_bytes();
+        let snapshot = Snapshot::from_bytes(&bytes).unwrap();
+        assert!(snapshot.verify());
+        let mut node = Blockchain::from_snapshot(snapshot.clone(), &checkpoint).unwrap();
+        assert_eq!(node.height(), 2);
+        assert_eq!(node.tip().hash, bc.tip().hash);
+        assert_eq!(node.state.root(), bc.state.root());
+
+        // 快照之后的区块仍需校验，状态继续累积
+        bc.state.insert(b"0xabcf", b"1".to_vec());
+        bc.add_block("0xabcf->0xabcd:1 btc".to_string());
+        let block = bc.tip().clone();
+        let mut forged = block.clone();
+        forged.tranxs = Some("0xabcf->0xabcd:100 btc".to_string());
+        assert_eq!(node.apply_block(forged), Err(ChainError::InvalidBlock(3)));
+        assert_eq!(node.apply_block(block), Ok(()));
+        assert_eq!(node.verify(), Ok(()));
+        assert_eq!(node.state.root(), bc.state.root());
+
+        // 快照之前的状态未知，不能重组到那里
+        let mut fork = Blockchain::new();
+        fork.blocks[0] = bc.blocks[0].clone();
+        for i in 0..4 {
+            fork.add_block(format!("0xabce->0xabcd:{} btc", i));
+        }
+        assert_eq!(node.reorg(0, fork.blocks[1..].to_vec()), Err(ChainError::MissingState(0)));
+
+        let mut bad = snapshot.clone();
+        bad.headers[1].txs_hash = "00".to_string();
+        assert_eq!(Blockchain::from_snapshot(bad, &checkpoint).err(), Some(ChainError::BadSnapshot));
+
+        // 自洽但不可信的快照：哈希重新计算过，但链尾与检查点不符
+        let mut other = Blockchain::new();
+        other.add_block("0xabcd->0xabcf:5 btc".to_string());
+        assert_eq!(Blockchain::from_snapshot(other.snapshot(), &checkpoint).err(),
+            Some(ChainError::BadSnapshot));
+
+        // 篡改状态后重新计算哈希，状态根对不上
+        let mut bad = snapshot;
+        bad.state[0].1 = b"1000".to_vec();
+        bad.hash = Snapshot::commit(bad.height, &bad.headers, &bad.state);
+        assert!(bad.verify());
+        assert_eq!(Blockchain::from_snapshot(bad, &checkpoint).err(), Some(ChainError::BadStateRoot(2)));
+    }
+
+    #[test]
+    fn test_save_load() {
+        let mut bc = Blockchain::new();
+        bc.add_block("0xabcd->0xabce:5 btc".to_string());
+        bc.add_block("0xabce->0xabcf:10 btc".to_string());
+
+        let path = std::env::temp_dir().join("rust_studying_test_chain.bin");
+        bc.save(&path).unwrap();
+        let loaded = Blockchain::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.verify(), Ok(()));
+        assert_eq!(loaded.tip().hash, bc.tip().hash);
+        assert_eq!(loaded.find_block(&bc.blocks[1].hash).unwrap().0, 1);
+        assert_eq!(loaded.account_history("0xabce").len(), 2);
+        assert_eq!(loaded.account_history("0xabcd").len(), 1);
+        assert_eq!(parse_tx("0xabcd->0xabce:5 btc"), Some(("0xabcd", "0xabce", "5 btc")));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_subscribe() {
+        let mut bc = Blockchain::new();
+        let rx = bc.subscribe();
+
+        bc.submit_tx("0xabcd->0xabce:5 btc".to_string());
+        assert!(bc.mine_pending());
+        assert!(!bc.mine_pending());
+
+        // 在创世区块后构造一个更长的分叉
+        let mut other = Blockchain::new();
+        other.blocks[0] = bc.blocks[0].clone();
+        other.add_block("0xabce->0xabcf:1 btc".to_string());
+        other.add_block("0xabcf->0xabcd:2 btc".to_string());
+        let branch = other.blocks[1..].to_vec();
+        assert_eq!(bc.reorg(0, branch[..1].to_vec()), Err(ChainError::StaleFork));
+        assert_eq!(bc.reorg(0, branch), Ok(()));
+        assert_eq!(bc.tip().hash, other.tip().hash);
+        assert_eq!(bc.mempool, vec!["0xabcd->0xabce:5 btc".to_string()]);
+
+        let events: Vec<String> = rx.try_iter().map(|e| match e {
+            ChainEvent::BlockConnected(h, _) => format!("connect {}", h),
+            ChainEvent::BlockDisconnected(h, _) => format!("disconnect {}", h),
+            ChainEvent::TxAdded(tx) => format!("add {}", tx),
+            ChainEvent::TxRemoved(tx) => format!("remove {}", tx),
+        }).collect();
+        assert_eq!(events, vec![
+            "add 0xabcd->0xabce:5 btc",
+            "remove 0xabcd->0xabce:5 btc",
+            "connect 1",
+            "disconnect 1",
+            "add 0xabcd->0xabce:5 btc",
+            "connect 1",
+            "connect 2",
+        ]);
+
+        // 接收端丢弃后不再发送
+        drop(rx);
+        bc.submit_tx("0xabcd->0xabcf:1 btc".to_string());
+        assert!(bc.subscribers.is_empty());
+    }
+
+    #[test]
+    fn test_state_root() {
+        let mut bc = Blockchain::new();
+        bc.state.insert(b"0xabcd", b"5".to_vec());
+        bc.state.insert(b"0xabce", b"10".to_vec());
+        bc.add_block("0xabcd->0xabce:5 btc".to_string());
+
+        // 轻客户端只需要区块头中的状态根和证明
+        let root = bc.state.root();
+        assert_eq!(bc.tip().header.state_root, to_hex(&root));
+        let proof = bc.state.prove(b"0xabce");
+        assert!(proof.verify(&root, b"0xabce", Some(b"10")));
+        assert!(bc.state.prove(b"0xabcf").verify(&root, b"0xabcf", None));
+
+        // 其他节点的区块必须带有匹配的状态变化
+        let mut node = Blockchain::new();
+        node.blocks[0] = bc.blocks[0].clone();
+        let mut forged = bc.tip().clone();
+        forged.state_changes.pop();
+        assert_eq!(node.apply_block(forged), Err(ChainError::BadStateRoot(1)));
+        assert_eq!(node.apply_block(bc.tip().clone()), Ok(()));
+        assert_eq!(node.state.root(), root);
+        assert_eq!(node.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_state_save_load() {
+        let mut bc = Blockchain::new();
+        bc.state.insert(b"0xabcd", b"5".to_vec());
+        bc.add_block("0xabcd->0xabce:5 btc".to_string());
+        bc.state.insert(b"0xabce", b"10".to_vec());
+        bc.add_block("0xabce->0xabcf:10 btc".to_string());
+
+        let path = std::env::temp_dir().join("rust_studying_test_chain_state.bin");
+        bc.save(&path).unwrap();
+        let mut loaded = Blockchain::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // 加载后的状态包含之前所有的账户，新区块的状态根也包含它们
+        assert_eq!(loaded.state.root(), bc.state.root());
+        loaded.state.insert(b"0xabcf", b"1".to_vec());
+        loaded.add_block("0xabcf->0xabcd:1 btc".to_string());
+        bc.state.insert(b"0xabcf", b"1".to_vec());
+        assert_eq!(loaded.tip().header.state_root, bc.state.root_hex());
+        assert_eq!(loaded.verify(), Ok(()));
+
+        // 篡改状态变化后校验失败
+        loaded.blocks[2].state_changes.clear();
+        assert_eq!(loaded.verify(), Err(ChainError::BadStateRoot(2)));
+    }
+
+    #[test]
+    fn test_state_reorg() {
+        let mut bc = Blockchain::new();
+        bc.state.insert(b"0xabcd", b"5".to_vec());
+        bc.add_block("0xabcd->0xabce:5 btc".to_string());
+        bc.state.insert(b"0xabcd", b"4".to_vec());
+        bc.add_block("0xabcd->0xabce:1 btc".to_string());
+
+        // 分叉中 0xabcd 从未出现，只有 0xabce
+        let mut other = Blockchain::new();
+        other.blocks[0] = bc.blocks[0].clone();
+        for i in 0..3 {
+            other.state.insert(b"0xabce", vec![i]);
+            other.add_block(format!("0xabcf->0xabce:{} btc", i));
+        }
+        let branch = other.blocks[1..].to_vec();
+
+        // 状态根不对的分叉被拒绝，链保持不变
+        let mut bad = branch.clone();
+        bad[2].state_changes.clear();
+        let tip = bc.tip().hash.clone();
+        assert_eq!(bc.reorg(0, bad), Err(ChainError::BadStateRoot(3)));
+        assert_eq!(bc.tip().hash, tip);
+        assert_eq!(bc.state.get(b"0xabcd"), Some(&b"4".to_vec()));
+
+        assert_eq!(bc.reorg(0, branch), Ok(()));
+        assert_eq!(bc.state.root(), other.state.root());
+        assert_eq!(bc.state.get(b"0xabcd"), None);
+        assert_eq!(bc.state.get(b"0xabce"), Some(&vec![2]));
+        assert_eq!(bc.verify(), Ok(()));
+
+        bc.state.insert(b"0xabcd", b"1".to_vec());
+        bc.add_block("0xabce->0xabcd:1 btc".to_string());
+        other.state.insert(b"0xabcd", b"1".to_vec());
+        assert_eq!(bc.tip().header.state_root, other.state.root_hex());
+    }
+}
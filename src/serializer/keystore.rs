@@ -0,0 +1,237 @@
+/*钱包私钥的加密存储
+先用口令通过 scrypt 或 PBKDF2 派生出 32 字节密钥：
+前 16 字节作为 AES-128-CTR 的密钥加密私钥，后 16 字节与密文一起计算 MAC
+解密时先校验 MAC，不一致就说明口令错误，最后以 JSON 格式保存到文件
+*/
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use crypto::aes::{self, KeySize};
+use crypto::hmac::Hmac;
+use crypto::pbkdf2::pbkdf2;
+use crypto::scrypt::{scrypt, ScryptParams};
+use crypto::sha2::Sha256;
+use crypto::util::fixed_time_eq;
+use rand::RngCore;
+use serde::{Serialize,Deserialize};
+use crate::serializer::serializer::{hash_bytes,to_hex,from_hex};
+
+const VERSION:u32 = 1;
+const CIPHER:&str = "aes-128-ctr";
+const DKLEN:usize = 32;
+
+// 密钥文件可能来自不可信的来源，限制派生的开销。
+// scrypt 占用内存 128 * r * N 字节，计算量再乘以 p
+const MAX_SCRYPT_MEMORY:u64 = 256 << 20;
+const MAX_SCRYPT_WORK:u64 = 1 << 30;
+// PBKDF2 的迭代次数上限，是默认值的 16 倍
+const MAX_PBKDF2_ROUNDS:u32 = 1 << 22;
+
+// 密钥文件错误类型
+#[derive(Debug,PartialEq)]
+pub enum KeystoreError {
+    WrongPassword,          // 口令错误（MAC 不匹配）
+    InvalidFormat,          // JSON 或字段格式错误
+    Unsupported,            // 不支持的版本、算法或参数
+    Io(io::ErrorKind),      // 读写文件失败
+}
+
+impl From<io::Error> for KeystoreError {
+    fn from(e:io::Error) -> Self {
+        KeystoreError::Io(e.kind())
+    }
+}
+
+// 密钥派生函数及其参数
+#[derive(Serialize,Deserialize,Debug,Clone,PartialEq)]
+#[serde(tag = "kdf", rename_all = "lowercase")]
+pub enum Kdf {
+    Scrypt { log_n:u8, r:u32, p:u32 },
+    Pbkdf2 { c:u32 },
+}
+
+impl Kdf {
+    // 默认参数，scrypt 的 N = 2^14
+    pub fn scrypt() -> Self {
+        Kdf::Scrypt{ log_n:14, r:8, p:1 }
+    }
+
+    pub fn pbkdf2() -> Self {
+        Kdf::Pbkdf2{ c:262144 }
+    }
+
+    fn derive(&self, password:&str, salt:&[u8]) -> Result<[u8;DKLEN], KeystoreError> {
+        let mut key = [0u8;DKLEN];
+        match *self {
+            Kdf::Scrypt{ log_n, r, p } => {
+                // 参数不合法时 ScryptParams::new 会 panic，先检查：
+                // N < 2^(16r)，p <= (2^32 - 1) * 32 / (128r)，再限制内存和计算量
+                if log_n == 0 || log_n >= 32 || r == 0 || r > 1024 || p == 0 {
+                    return Err(KeystoreError::Unsupported);
+                }
+                let (r, p) = (r as u64, p as u64);
+                let memory = (128 * r) << log_n;
+                if log_n as u64 >= r * 16 || r * p >= 1 << 30
+                    || memory > MAX_SCRYPT_MEMORY || memory * p > MAX_SCRYPT_WORK {
+                    return Err(KeystoreError::Unsupported);
+                }
+                let (r, p) = (r as u32, p as u32);
+                scrypt(password.as_bytes(), salt, &ScryptParams::new(log_n, r, p), &mut key);
+            },
+            Kdf::Pbkdf2{ c } => {
+                if c == 0 || c > MAX_PBKDF2_ROUNDS {
+                    return Err(KeystoreError::Unsupported);
+                }
+                let mut mac = Hmac::new(Sha256::new(), password.as_bytes());
+                pbkdf2(&mut mac, salt, c, &mut key);
+            },
+        }
+        Ok(key)
+    }
+}
+
+// 密钥文件内容，二进制字段都以十六进制保存
+#[derive(Serialize,Deserialize,Debug,Clone,PartialEq)]
+pub struct Keystore {
+    pub version:u32,
+    pub cipher:String,
+    pub ciphertext:String,
+    pub iv:String,
+    pub salt:String,
+    #[serde(flatten)]
+    pub kdf:Kdf,
+    pub mac:String,
+}
+
+// MAC = SHA3(派生密钥后 16 字节 || 密文)
+fn mac(key:&[u8], ciphertext:&[u8]) -> [u8;32] {
+    let mut buf = key[16..].to_vec();
+    buf.extend_from_slice(ciphertext);
+    hash_bytes(&buf)
+}
+
+// AES-128-CTR 加解密是同一个操作
+fn aes_ctr(key:&[u8], iv:&[u8], input:&[u8]) -> Vec<u8> {
+    let mut output = vec![0u8;input.len()];
+    aes::ctr(KeySize::KeySize128, &key[..16], iv).process(input, &mut output);
+    output
+}
+
+impl Keystore {
+    // 用口令加密私钥
+    pub fn encrypt(secret:&[u8], password:&str, kdf:Kdf) -> Result<Self, KeystoreError> {
+        let mut salt = [0u8;32];
+        let mut iv = [0u8;16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut iv);
+
+        let key = kdf.derive(password, &salt)?;
+        let ciphertext = aes_ctr(&key, &iv, secret);
+        Ok(Keystore{
+            version: VERSION,
+            cipher: CIPHER.to_string(),
+            mac: to_hex(&mac(&key, &ciphertext)),
+            ciphertext: to_hex(&ciphertext),
+            iv: to_hex(&iv),
+            salt: to_hex(&salt),
+            kdf,
+        })
+    }
+
+    // 用口令解密私钥，口令错误时返回 WrongPassword
+    pub fn decrypt(&self, password:&str) -> Result<Vec<u8>, KeystoreError> {
+        if self.version != VERSION || self.cipher != CIPHER {
+            return Err(KeystoreError::Unsupported);
+        }
+        let field = |s:&str| from_hex(s).ok_or(KeystoreError::InvalidFormat);
+        let ciphertext = field(&self.ciphertext)?;
+        let iv = field(&self.iv)?;
+        let salt = field(&self.salt)?;
+        let expected = field(&self.mac)?;
+        if iv.len() != 16 {
+            return Err(KeystoreError::InvalidFormat);
+        }
+
+        let key = self.kdf.derive(password, &salt)?;
+        if !fixed_time_eq(&mac(&key, &ciphertext), &expected) {
+            return Err(KeystoreError::WrongPassword);
+        }
+        Ok(aes_ctr(&key, &iv, &ciphertext))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(json:&str) -> Result<Self, KeystoreError> {
+        serde_json::from_str(json).map_err(|_| KeystoreError::InvalidFormat)
+    }
+}
+
+// 加密私钥并保存到文件
+pub fn save<P: AsRef<Path>>(path:P, secret:&[u8], password:&str, kdf:Kdf) -> Result<(), KeystoreError> {
+    let keystore = Keystore::encrypt(secret, password, kdf)?;
+    fs::write(path, keystore.to_json())?;
+    Ok(())
+}
+
+// 从文件读取并解密私钥
+pub fn load<P: AsRef<Path>>(path:P, password:&str) -> Result<Vec<u8>, KeystoreError> {
+    let json = fs::read_to_string(path)?;
+    Keystore::from_json(&json)?.decrypt(password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试用较小的参数，避免太慢
+    const FAST_SCRYPT:Kdf = Kdf::Scrypt{ log_n:4, r:8, p:1 };
+    const FAST_PBKDF2:Kdf = Kdf::Pbkdf2{ c:16 };
+
+    #[test]
+    fn it_works() {
+        let secret = [7u8;32];
+        for kdf in [FAST_SCRYPT, FAST_PBKDF2] {
+            let ks = Keystore::encrypt(&secret, "password", kdf).unwrap();
+            let ks = Keystore::from_json(&ks.to_json()).unwrap();
+            assert_eq!(ks.decrypt("password").unwrap(), secret.to_vec());
+            assert_eq!(ks.decrypt("wrong"), Err(KeystoreError::WrongPassword));
+        }
+    }
+
+    #[test]
+    fn test_save_load() {
+        let path = std::env::temp_dir().join("rust_studying_test_keystore.json");
+        save(&path, b"secret key", "password", FAST_SCRYPT).unwrap();
+        assert_eq!(load(&path, "password").unwrap(), b"secret key".to_vec());
+        assert_eq!(load(&path, "Password"), Err(KeystoreError::WrongPassword));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(load(&path, "password"), Err(KeystoreError::Io(io::ErrorKind::NotFound)));
+        assert_eq!(Keystore::from_json("{}"), Err(KeystoreError::InvalidFormat));
+    }
+
+    #[test]
+    fn test_kdf_limits() {
+        // 不合法或开销过大的参数返回错误，不会 panic 或占用大量资源
+        let ks = Keystore::encrypt(b"secret", "password", FAST_SCRYPT).unwrap();
+        let cases = [
+            r#"{"kdf":"scrypt","log_n":17,"r":1,"p":1}"#,
+            r#"{"kdf":"scrypt","log_n":31,"r":1024,"p":1}"#,
+            r#"{"kdf":"scrypt","log_n":14,"r":8,"p":4294967295}"#,
+            r#"{"kdf":"scrypt","log_n":20,"r":8,"p":1}"#,
+            r#"{"kdf":"pbkdf2","c":4294967295}"#,
+        ];
+        let path = std::env::temp_dir().join("rust_studying_test_keystore_limits.json");
+        for kdf in cases {
+            let mut bad = ks.clone();
+            bad.kdf = serde_json::from_str(kdf).unwrap();
+            fs::write(&path, bad.to_json()).unwrap();
+            assert_eq!(load(&path, "password"), Err(KeystoreError::Unsupported), "{}", kdf);
+        }
+        fs::remove_file(&path).unwrap();
+        assert!(Keystore::encrypt(b"secret", "password", Kdf::Scrypt{ log_n:17, r:1, p:1 }).is_err());
+    }
+}
@@ -1,4 +1,6 @@
 pub mod serializer;
 pub mod block;
 pub mod blockchain;
+pub mod smt;
+pub mod keystore;
 
@@ -14,4 +14,28 @@ pub fn hash_str(value:&[u8]) -> String{
     let mut hasher = Sha3::sha3_256();
     hasher.input(value);
     hasher.result_str()
-}
\ No newline at end of file
+}
+
+// 计算哈希值并以字节数组形式返回
+pub fn hash_bytes(value:&[u8]) -> [u8;32]{
+    let mut hasher = Sha3::sha3_256();
+    hasher.input(value);
+    let mut out = [0u8;32];
+    hasher.result(&mut out);
+    out
+}
+
+// 字节转十六进制字符串
+pub fn to_hex(bytes:&[u8]) -> String{
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// 十六进制字符串转字节，格式错误时返回 None
+pub fn from_hex(s:&str) -> Option<Vec<u8>>{
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    (0..s.len()).step_by(2)
+        .map(|i| s.get(i..i + 2).and_then(|h| u8::from_str_radix(h, 16).ok()))
+        .collect()
+}
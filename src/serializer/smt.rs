@@ -0,0 +1,354 @@
+/*稀疏默克尔树（Sparse Merkle Tree），用于对账户状态做承诺
+键经过哈希后得到 256 位路径，每一位决定向左(0)还是向右(1)，值存放在叶子上
+空子树的哈希固定为全 0，所以只需计算有数据的分支。
+非空子树的哈希按 (深度, 路径前缀) 缓存，插入、删除时只重算路径上的 256 个节点，取根是 O(1)
+证明由路径上的兄弟节点哈希组成，既能证明键存在，也能证明键不存在
+*/
+
+use std::collections::{BTreeMap,HashMap};
+use crate::serializer::serializer::{hash_bytes,to_hex};
+
+// 树的深度，等于路径的位数
+const DEPTH:usize = 256;
+
+// 空子树的哈希
+pub const EMPTY_ROOT:[u8;32] = [0u8;32];
+
+// 叶子节点和内部节点加上不同的前缀，防止二者混淆
+const LEAF_PREFIX:u8 = 0;
+const NODE_PREFIX:u8 = 1;
+
+// 状态变化：叶子路径及新值，None 表示删除
+pub type StateChanges = Vec<([u8;32], Option<Vec<u8>>)>;
+
+// 默克尔证明：bitmap 中第 i 位为 1 表示第 i 层的兄弟节点非空，
+// siblings 按从根到叶子的顺序只保存非空的兄弟节点哈希
+#[derive(Debug,Clone,PartialEq)]
+pub struct Proof {
+    pub bitmap:[u8;32],
+    pub siblings:Vec<[u8;32]>,
+}
+
+#[derive(Debug,Clone,Default)]
+pub struct SparseMerkleTree {
+    leaves:BTreeMap<[u8;32], Vec<u8>>,
+    nodes:HashMap<(usize, [u8;32]), [u8;32]>,     // (深度, 路径前缀) -> 非空子树的哈希
+}
+
+// 取路径第 i 位
+fn bit(path:&[u8;32], i:usize) -> bool {
+    path[i / 8] & (0x80 >> (i % 8)) != 0
+}
+
+// 路径的前 depth 位，其余位为 0
+fn prefix(path:&[u8;32], depth:usize) -> [u8;32] {
+    let mut p = [0u8;32];
+    let full = depth / 8;
+    p[..full].copy_from_slice(&path[..full]);
+    if depth % 8 != 0 {
+        p[full] = path[full] & !(0xffu8 >> (depth % 8));
+    }
+    p
+}
+
+// 深度为 depth + 1 的兄弟节点的前缀，即前 depth + 1 位中最后一位取反
+fn sibling_prefix(path:&[u8;32], depth:usize) -> [u8;32] {
+    let mut p = prefix(path, depth + 1);
+    p[depth / 8] ^= 0x80 >> (depth % 8);
+    p
+}
+
+fn leaf_hash(path:&[u8;32], value:&[u8]) -> [u8;32] {
+    let mut buf = Vec::with_capacity(1 + 32 + value.len());
+    buf.push(LEAF_PREFIX);
+    buf.extend_from_slice(path);
+    buf.extend_from_slice(value);
+    hash_bytes(&buf)
+}
+
+fn node_hash(left:&[u8;32], right:&[u8;32]) -> [u8;32] {
+    if *left == EMPTY_ROOT && *right == EMPTY_ROOT {
+        return EMPTY_ROOT;
+    }
+    let mut buf = [0u8;65];
+    buf[0] = NODE_PREFIX;
+    buf[1..33].copy_from_slice(left);
+    buf[33..].copy_from_slice(right);
+    hash_bytes(&buf)
+}
+
+impl SparseMerkleTree {
+    pub fn new() -> Self {
+        SparseMerkleTree{ leaves:BTreeMap::new(), nodes:HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    // 键的路径
+    pub fn path(key:&[u8]) -> [u8;32] {
+        hash_bytes(key)
+    }
+
+    pub fn insert(&mut self, key:&[u8], value:Vec<u8>) -> Option<Vec<u8>> {
+        let path = Self::path(key);
+        let old = self.leaves.insert(path, value);
+        self.update(&path);
+        old
+    }
+
+    pub fn get(&self, key:&[u8]) -> Option<&Vec<u8>> {
+        self.leaves.get(&Self::path(key))
+    }
+
+    pub fn delete(&mut self, key:&[u8]) -> Option<Vec<u8>> {
+        let path = Self::path(key);
+        let old = self.leaves.remove(&path);
+        self.update(&path);
+        old
+    }
+
+    // 缓存的子树哈希，不在缓存中的是空子树
+    fn node(&self, depth:usize, prefix:&[u8;32]) -> [u8;32] {
+        self.nodes.get(&(depth, *prefix)).copied().unwrap_or(EMPTY_ROOT)
+    }
+
+    // 叶子变化后从下往上重算路径上的节点，空子树从缓存中删除
+    fn update(&mut self, path:&[u8;32]) {
+        let mut hash = match self.leaves.get(path) {
+            Some(v) => leaf_hash(path, v),
+            None => EMPTY_ROOT,
+        };
+        for depth in (0..=DEPTH).rev() {
+            if depth < DEPTH {
+                let sibling = self.node(depth + 1, &sibling_prefix(path, depth));
+                hash = if bit(path, depth) {
+                    node_hash(&sibling, &hash)
+                } else {
+                    node_hash(&hash, &sibling)
+                };
+            }
+            let key = (depth, prefix(path, depth));
+            if hash == EMPTY_ROOT {
+                self.nodes.remove(&key);
+            } else {
+                self.nodes.insert(key, hash);
+            }
+        }
+    }
+
+    // 按路径排序的全部叶子
+    pub fn leaves(&self) -> impl Iterator<Item = (&[u8;32], &Vec<u8>)> {
+        self.leaves.iter()
+    }
+
+    // 由全部叶子从头建树，每个节点只计算一次
+    pub fn from_leaves<I: IntoIterator<Item = ([u8;32], Vec<u8>)>>(leaves:I) -> Self {
+        let leaves: BTreeMap<[u8;32], Vec<u8>> = leaves.into_iter().collect();
+        let mut nodes = HashMap::new();
+        Self::build(&mut nodes, &leaves.iter().collect::<Vec<_>>(), 0);
+        SparseMerkleTree{ leaves, nodes }
+    }
+
+    // 从当前状态变为 other 所需的变化
+    pub fn diff(&self, other:&SparseMerkleTree) -> StateChanges {
+        let mut changes: StateChanges = other.leaves.iter()
+            .filter(|(path, value)| self.leaves.get(*path) != Some(*value))
+            .map(|(path, value)| (*path, Some(value.clone())))
+            .collect();
+        changes.extend(self.leaves.keys()
+            .filter(|path| !other.leaves.contains_key(*path))
+            .map(|path| (*path, None)));
+        changes
+    }
+
+    // 应用状态变化，返回撤销这些变化所需的变化
+    pub fn apply(&mut self, changes:&[([u8;32], Option<Vec<u8>>)]) -> StateChanges {
+        let mut undo: StateChanges = changes.iter().map(|(path, value)| {
+            let old = match value {
+                Some(v) => self.leaves.insert(*path, v.clone()),
+                None => self.leaves.remove(path),
+            };
+            self.update(path);
+            (*path, old)
+        }).collect();
+        // 同一路径出现多次时，要按相反的顺序撤销
+        undo.reverse();
+        undo
+    }
+
+    pub fn root(&self) -> [u8;32] {
+        self.node(0, &EMPTY_ROOT)
+    }
+
+    pub fn root_hex(&self) -> String {
+        to_hex(&self.root())
+    }
+
+    // 计算深度为 depth 的子树哈希并放入缓存，leaves 已按路径排序且前 depth 位相同
+    fn build(nodes:&mut HashMap<(usize, [u8;32]), [u8;32]>,
+        leaves:&[(&[u8;32], &Vec<u8>)], depth:usize) -> [u8;32] {
+        let hash = match leaves.len() {
+            0 => return EMPTY_ROOT,
+            1 if depth == DEPTH => leaf_hash(leaves[0].0, leaves[0].1),
+            _ => {
+                let mid = leaves.partition_point(|(p, _)| !bit(p, depth));
+                let left = Self::build(nodes, &leaves[..mid], depth + 1);
+                let right = Self::build(nodes, &leaves[mid..], depth + 1);
+                node_hash(&left, &right)
+            },
+        };
+        nodes.insert((depth, prefix(leaves[0].0, depth)), hash);
+        hash
+    }
+
+    // 生成证明，键不存在时得到的是不存在证明
+    pub fn prove(&self, key:&[u8]) -> Proof {
+        let path = Self::path(key);
+        let mut proof = Proof{ bitmap:[0u8;32], siblings:Vec::new() };
+
+        // 从根往下走，每一层记录另一侧子树的哈希，所在子树为空时下面的兄弟节点也都为空
+        for depth in 0..DEPTH {
+            if self.node(depth, &prefix(&path, depth)) == EMPTY_ROOT {
+                break;
+            }
+            let sibling = self.node(depth + 1, &sibling_prefix(&path, depth));
+            if sibling != EMPTY_ROOT {
+                proof.bitmap[depth / 8] |= 0x80 >> (depth % 8);
+                proof.siblings.push(sibling);
+            }
+        }
+        proof
+    }
+}
+
+impl Proof {
+    // 校验证明：value 为 Some 时证明键存在且值相等，为 None 时证明键不存在
+    pub fn verify(&self, root:&[u8;32], key:&[u8], value:Option<&[u8]>) -> bool {
+        let path = SparseMerkleTree::path(key);
+        let mut hash = match value {
+            Some(v) => leaf_hash(&path, v),
+            None => EMPTY_ROOT,
+        };
+
+        // 从叶子往上逐层计算
+        let mut siblings = self.siblings.iter().rev();
+        for depth in (0..DEPTH).rev() {
+            let sibling = if bit(&self.bitmap, depth) {
+                match siblings.next() {
+                    Some(s) => *s,
+                    None => return false,
+                }
+            } else {
+                EMPTY_ROOT
+            };
+            hash = if bit(&path, depth) {
+                node_hash(&sibling, &hash)
+            } else {
+                node_hash(&hash, &sibling)
+            };
+        }
+        siblings.next().is_none() && hash == *root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn it_works() {
+        let mut smt = SparseMerkleTree::new();
+        assert_eq!(smt.root(), EMPTY_ROOT);
+
+        smt.insert(b"0xabcd", b"5".to_vec());
+        smt.insert(b"0xabce", b"10".to_vec());
+        let root = smt.root();
+        assert_eq!(smt.get(b"0xabcd"), Some(&b"5".to_vec()));
+        assert_eq!(smt.insert(b"0xabcd", b"7".to_vec()), Some(b"5".to_vec()));
+        assert_ne!(smt.root(), root);
+        assert_eq!(smt.delete(b"0xabcd"), Some(b"7".to_vec()));
+        assert_eq!(smt.len(), 1);
+
+        // 根只与内容有关，与插入顺序无关
+        smt.insert(b"0xabcd", b"5".to_vec());
+        assert_eq!(smt.root(), root);
+        smt.delete(b"0xabcd");
+        smt.delete(b"0xabce");
+        assert_eq!(smt.root(), EMPTY_ROOT);
+        assert!(smt.nodes.is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut a = SparseMerkleTree::new();
+        a.insert(b"0xabcd", b"5".to_vec());
+        a.insert(b"0xabce", b"10".to_vec());
+        let mut b = a.clone();
+        b.insert(b"0xabcd", b"7".to_vec());
+        b.delete(b"0xabce");
+        b.insert(b"0xabcf", b"1".to_vec());
+
+        let changes = a.diff(&b);
+        assert_eq!(changes.len(), 3);
+        let (root_a, root_b) = (a.root(), b.root());
+        let undo = a.apply(&changes);
+        assert_eq!(a.root(), root_b);
+        a.apply(&undo);
+        assert_eq!(a.root(), root_a);
+
+        let copy = SparseMerkleTree::from_leaves(b.leaves().map(|(p, v)| (*p, v.clone())));
+        assert_eq!(copy.root(), root_b);
+        assert!(b.diff(&copy).is_empty());
+    }
+
+    #[test]
+    fn test_proof() {
+        let mut smt = SparseMerkleTree::new();
+        for i in 0..20 {
+            smt.insert(format!("0x{:04x}", i).as_bytes(), vec![i as u8]);
+        }
+        let root = smt.root();
+
+        // 存在证明
+        let proof = smt.prove(b"0x0007");
+        assert!(proof.verify(&root, b"0x0007", Some(&[7])));
+        assert!(!proof.verify(&root, b"0x0007", Some(&[8])));
+        assert!(!proof.verify(&root, b"0x0007", None));
+
+        // 不存在证明
+        let proof = smt.prove(b"0xffff");
+        assert!(proof.verify(&root, b"0xffff", None));
+        assert!(!proof.verify(&root, b"0xffff", Some(&[0])));
+
+        // 空树
+        let empty = SparseMerkleTree::new();
+        assert!(empty.prove(b"0x0007").verify(&EMPTY_ROOT, b"0x0007", None));
+    }
+
+    #[test]
+    fn test_incremental_root() {
+        // 逐个插入、修改、删除后的根和缓存，与由全部叶子从头计算的一致
+        let mut smt = SparseMerkleTree::new();
+        for i in 0..2000u32 {
+            smt.insert(&i.to_be_bytes(), vec![i as u8]);
+        }
+        for i in (0..2000u32).step_by(10) {
+            smt.insert(&i.to_be_bytes(), vec![1, 2, 3]);
+            smt.delete(&(i + 1).to_be_bytes());
+        }
+        let full = SparseMerkleTree::from_leaves(smt.leaves().map(|(p, v)| (*p, v.clone())));
+        assert_eq!(smt.len(), 1800);
+        assert_eq!(smt.root(), full.root());
+        assert_eq!(smt.nodes, full.nodes);
+
+        let key = 5u32.to_be_bytes();
+        assert!(smt.prove(&key).verify(&full.root(), &key, Some(&[5])));
+        let key = 11u32.to_be_bytes();
+        assert!(smt.prove(&key).verify(&full.root(), &key, None));
+    }
+}